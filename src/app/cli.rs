@@ -0,0 +1,115 @@
+//! Headless command-line interface for scripts and machines without a display.
+//!
+//! ```text
+//! kinematicsolver solve <design.json> [--steps N] [--out curve.csv]
+//! kinematicsolver render <design.json> <out.png|out.svg|out.pdf>
+//! ```
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use gtk::glib::ExitCode;
+use kinematicsolver::{export, Design, FourBar, LinkMotion};
+
+use super::export::{export_drawing, Format};
+
+const USAGE: &str = "\
+usage: kinematicsolver solve <design.json> [--steps N] [--out curve.csv]
+       kinematicsolver render <design.json> <out.png|out.svg|out.pdf>
+
+Without arguments the graphical editor is started.";
+
+/// Number of crank angles sampled by `solve` unless `--steps` is given.
+const DEFAULT_STEPS: usize = 360;
+
+/// Runs the command named in `args` (without the program name).
+///
+/// Returns `None` if `args` does not name a command, in which case the
+/// caller should start the GUI.
+pub fn run(args: &[String]) -> Option<ExitCode> {
+    let result = match args.first().map(String::as_str) {
+        Some("solve") => solve(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            return Some(ExitCode::SUCCESS);
+        }
+        _ => return None,
+    };
+
+    Some(match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("kinematicsolver: {err}");
+            ExitCode::FAILURE
+        }
+    })
+}
+
+/// Writes the motion over one crank revolution as CSV.
+fn solve(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut design = None;
+    let mut steps = DEFAULT_STEPS;
+    let mut out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => {
+                let value = args.next().ok_or("--steps needs a value")?;
+                steps = match value.parse() {
+                    Ok(steps) if steps > 0 => steps,
+                    _ => return Err(format!("invalid number of steps: {value}").into()),
+                };
+            }
+            "--out" => out = Some(args.next().ok_or("--out needs a file name")?),
+            _ if design.is_none() => design = Some(arg),
+            _ => return Err(format!("unexpected argument: {arg}\n\n{USAGE}").into()),
+        }
+    }
+
+    let design = design.ok_or_else(|| format!("missing design file\n\n{USAGE}"))?;
+    let design = load(design)?;
+    match out {
+        Some(path) => write_csv(&design, steps, BufWriter::new(File::create(path)?))?,
+        None => write_csv(&design, steps, io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+/// Writes the design's four-bar or general linkage with its tracers.
+fn write_csv(design: &Design, steps: usize, out: impl Write) -> io::Result<()> {
+    match &design.linkage {
+        Some(linkage) => export::write_linkage_csv(linkage, &design.tracers, steps, out),
+        None => export::write_csv(
+            &FourBar::from_pose(&design.pose()),
+            &design.tracers,
+            steps,
+            out,
+        ),
+    }
+}
+
+/// Draws the mechanism to an image, in the format given by its extension.
+fn render(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let [design, out] = args else {
+        return Err(format!("render needs a design file and an output file\n\n{USAGE}").into());
+    };
+
+    let out = Path::new(out);
+    let format = Format::from_path(out)
+        .ok_or_else(|| format!("cannot render to {}: use .png, .svg or .pdf", out.display()))?;
+    let design = load(design)?;
+    let crank = LinkMotion {
+        omega: 1.0,
+        alpha: 0.0,
+    };
+    export_drawing(out, format, &design, crank)?;
+    Ok(())
+}
+
+fn load(path: &str) -> Result<Design, Box<dyn Error>> {
+    Design::load(path).map_err(|err| format!("{path}: {err}").into())
+}
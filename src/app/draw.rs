@@ -0,0 +1,838 @@
+//! Drawing of the mechanism with cairo, shared by the window and exports.
+
+use cairo::Context;
+use kinematicsolver::linkage::GROUND;
+use kinematicsolver::synthesis::{dyad_construction, pole_triangle};
+use kinematicsolver::{
+    CouplerPosition, DisplaySettings, FourBar, Link, LinkMotion, Linkage, Motion, MotionChoices,
+    Point, PointMotion, Pose, SolveError, Tracer, TransmissionAngles,
+};
+
+use super::view::View;
+
+pub const JOINT_RADIUS: f64 = 10.0;
+pub const STROKE_WIDTH: f64 = 1.5;
+const SUPPORT_TRIANGLE_WIDTH: f64 = 50.0;
+const SUPPORT_TRIANGLE_HEIGHT: f64 = 30.0;
+const SUPPORT_BASE_WIDTH: f64 = 70.0;
+const SUPPORT_LINE_HEIGHT: f64 = 20.0;
+const SUPPORT_LINE_WIDTH: f64 = 5.0;
+const SUPPORT_LINE_MARGIN: f64 = 1.0;
+const SUPPORT_LINE_COUNT: usize = 5;
+pub const COUPLER_CURVE_RESOLUTION: usize = 1000;
+const TRANSMISSION_ARC_RADIUS: f64 = 30.0;
+const VECTOR_ARROW_LENGTH: f64 = 60.0;
+const VECTOR_ARROW_HEAD: f64 = 8.0;
+const TRACED_POINT_RADIUS: f64 = 5.0;
+const BLOCK_LENGTH: f64 = 40.0;
+const BLOCK_WIDTH: f64 = 24.0;
+const RAIL_HATCH_SPACING: f64 = 12.0;
+const RAIL_HATCH_LENGTH: f64 = 8.0;
+/// Half the size of the cross marking a precision point.
+const PRECISION_POINT_SIZE: f64 = 8.0;
+/// Length of the arrow showing the direction of a coupler position, on the
+/// screen.
+pub const POSITION_ARROW_LENGTH: f64 = 50.0;
+/// How far construction lines reach past what they join, on the screen.
+const CONSTRUCTION_LINE_OVERHANG: f64 = 60.0;
+/// Colours of the crank and the rocker in the motion synthesis
+/// construction.
+const DYAD_COLORS: [(f64, f64, f64); 2] = [(0.0, 0.4, 0.8), (0.9, 0.5, 0.0)];
+/// Crank positions per revolution when tracing the paths of a general
+/// linkage, which is solved numerically and so fewer than for a four-bar.
+pub const LINKAGE_PATH_RESOLUTION: usize = 360;
+
+/// A general linkage solved over one revolution of its crank, see
+/// [`Linkage::revolution`], to draw the paths of its points from.
+pub type Revolution = [(f64, Result<Linkage, SolveError>)];
+
+/// What is drawn and plotted of a four-bar over one revolution of its
+/// crank, which depends only on its dimensions and branch and not on where
+/// the crank is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FourBarPaths {
+    /// The four-bar the rest was found for.
+    pub four_bar: FourBar,
+    /// See [`FourBar::coupler_curve`].
+    pub coupler_curve: Vec<Vec<Point>>,
+    /// The coupler curve of the same four-bar on its other branch.
+    pub other_coupler_curve: Vec<Vec<Point>>,
+    /// The four-bar at every step of [`FourBar::revolution`] from a crank
+    /// angle of zero, `None` where it cannot be assembled.
+    pub poses: Vec<Option<Pose>>,
+    /// See [`FourBar::transmission_angles`].
+    pub transmission: Option<TransmissionAngles>,
+}
+
+impl FourBarPaths {
+    pub fn new(four_bar: FourBar) -> Self {
+        let other = FourBar {
+            branch: four_bar.branch.opposite(),
+            ..four_bar
+        };
+        FourBarPaths {
+            four_bar,
+            coupler_curve: four_bar.coupler_curve(0.0, COUPLER_CURVE_RESOLUTION),
+            other_coupler_curve: other.coupler_curve(0.0, COUPLER_CURVE_RESOLUTION),
+            poses: four_bar
+                .revolution(0.0, COUPLER_CURVE_RESOLUTION)
+                .map(|(_, pose)| pose.ok())
+                .collect(),
+            transmission: four_bar.transmission_angles(0.0, COUPLER_CURVE_RESOLUTION),
+        }
+    }
+}
+
+fn draw_support(context: &Context, p: (f64, f64)) -> Result<(), cairo::Error> {
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+
+    context.arc(p.0, p.1, JOINT_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
+    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+
+    context.move_to(
+        p.0 - JOINT_RADIUS / 2.0_f64.sqrt(),
+        p.1 + JOINT_RADIUS / 2.0_f64.sqrt(),
+    );
+    context.line_to(
+        p.0 - SUPPORT_TRIANGLE_WIDTH / 2.0,
+        p.1 + SUPPORT_TRIANGLE_HEIGHT,
+    );
+
+    context.move_to(
+        p.0 + JOINT_RADIUS / 2.0_f64.sqrt(),
+        p.1 + JOINT_RADIUS / 2.0_f64.sqrt(),
+    );
+    context.line_to(
+        p.0 + SUPPORT_TRIANGLE_WIDTH / 2.0,
+        p.1 + SUPPORT_TRIANGLE_HEIGHT,
+    );
+
+    context.move_to(
+        p.0 - SUPPORT_BASE_WIDTH / 2.0,
+        p.1 + SUPPORT_TRIANGLE_HEIGHT,
+    );
+    context.line_to(
+        p.0 + SUPPORT_BASE_WIDTH / 2.0,
+        p.1 + SUPPORT_TRIANGLE_HEIGHT,
+    );
+
+    for i in 0..SUPPORT_LINE_COUNT {
+        context.move_to(
+            p.0 - SUPPORT_BASE_WIDTH / 2.0
+                + SUPPORT_LINE_MARGIN
+                + (i as f64 / SUPPORT_LINE_COUNT as f64)
+                    * (SUPPORT_BASE_WIDTH - SUPPORT_LINE_MARGIN),
+            p.1 + SUPPORT_TRIANGLE_HEIGHT + SUPPORT_LINE_HEIGHT,
+        );
+        context.rel_line_to(SUPPORT_LINE_WIDTH, -SUPPORT_LINE_HEIGHT);
+    }
+
+    context.stroke()?;
+    context.restore()
+}
+
+fn draw_joint(context: &Context, p: (f64, f64)) -> Result<(), cairo::Error> {
+    context.save()?;
+
+    context.set_line_width(STROKE_WIDTH);
+    context.arc(p.0, p.1, JOINT_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
+    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+
+    context.restore()
+}
+
+fn draw_connecting_line(
+    context: &Context,
+    p1: (f64, f64),
+    p2: (f64, f64),
+) -> Result<(), cairo::Error> {
+    context.save()?;
+
+    context.set_line_width(STROKE_WIDTH);
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.move_to(p1.0, p1.1);
+    context.line_to(p2.0, p2.1);
+    context.stroke()?;
+
+    context.restore()
+}
+
+fn draw_transmission_angle(
+    context: &Context,
+    pose: &Pose,
+    min_angle: f64,
+) -> Result<(), cairo::Error> {
+    context.save()?;
+
+    let p = pose.rocker_pin;
+    let a1 = (pose.crank_pin.1 - p.1).atan2(pose.crank_pin.0 - p.0);
+    let a2 = (pose.rocker_pivot.1 - p.1).atan2(pose.rocker_pivot.0 - p.0);
+    let mu = pose.transmission_angle();
+
+    if mu < min_angle || mu > std::f64::consts::PI - min_angle {
+        context.set_source_rgba(0.8, 0.0, 0.0, 0.8);
+    } else {
+        context.set_source_rgba(0.0, 0.5, 0.0, 0.8);
+    }
+    context.set_line_width(STROKE_WIDTH);
+
+    // always take the inner side of the angle
+    if (a2 - a1).rem_euclid(2.0 * std::f64::consts::PI) < std::f64::consts::PI {
+        context.arc(p.0, p.1, TRANSMISSION_ARC_RADIUS, a1, a2);
+    } else {
+        context.arc_negative(p.0, p.1, TRANSMISSION_ARC_RADIUS, a1, a2);
+    }
+    context.stroke()?;
+
+    let bisector = (pose.crank_pin.1 - p.1 + pose.rocker_pivot.1 - p.1)
+        .atan2(pose.crank_pin.0 - p.0 + pose.rocker_pivot.0 - p.0);
+    context.set_font_size(14.0);
+    context.move_to(
+        p.0 + 1.5 * TRANSMISSION_ARC_RADIUS * bisector.cos(),
+        p.1 + 1.5 * TRANSMISSION_ARC_RADIUS * bisector.sin(),
+    );
+    context.show_text(&format!("{:.1}°", mu.to_degrees()))?;
+
+    context.restore()
+}
+
+fn draw_arrow(
+    context: &Context,
+    p: (f64, f64),
+    v: (f64, f64),
+    color: (f64, f64, f64),
+) -> Result<(), cairo::Error> {
+    let length = v.0.hypot(v.1);
+    if length < 1e-9 {
+        return Ok(());
+    }
+
+    context.save()?;
+
+    context.set_line_width(STROKE_WIDTH);
+    context.set_source_rgba(color.0, color.1, color.2, 0.8);
+    context.move_to(p.0, p.1);
+    context.line_to(p.0 + v.0, p.1 + v.1);
+    context.stroke()?;
+
+    let (ux, uy) = (v.0 / length, v.1 / length);
+    let head = VECTOR_ARROW_HEAD.min(length);
+    context.move_to(p.0 + v.0, p.1 + v.1);
+    context.line_to(
+        p.0 + v.0 - head * ux + 0.5 * head * uy,
+        p.1 + v.1 - head * uy - 0.5 * head * ux,
+    );
+    context.line_to(
+        p.0 + v.0 - head * ux - 0.5 * head * uy,
+        p.1 + v.1 - head * uy + 0.5 * head * ux,
+    );
+    context.close_path();
+    context.fill()?;
+
+    context.restore()
+}
+
+/// Draws velocity and/or acceleration arrows at the moving joints, scaled so
+/// that the crank pin's arrow is `VECTOR_ARROW_LENGTH` long.
+///
+/// `pose` is in screen coordinates, `motion` in world coordinates.
+fn draw_motion(
+    context: &Context,
+    view: &View,
+    pose: &Pose,
+    motion: &Motion,
+    display: &DisplaySettings,
+) -> Result<(), cairo::Error> {
+    let on_screen = |m: PointMotion| PointMotion {
+        velocity: view.vector_to_screen(m.velocity),
+        acceleration: view.vector_to_screen(m.acceleration),
+    };
+    let joints = [
+        (pose.crank_pin, on_screen(motion.crank_pin)),
+        (pose.rocker_pin, on_screen(motion.rocker_pin)),
+        (pose.coupler_point, on_screen(motion.coupler_point)),
+    ];
+    let scale = |v: (f64, f64)| {
+        let length = v.0.hypot(v.1);
+        if length > 1e-9 {
+            VECTOR_ARROW_LENGTH / length
+        } else {
+            1.0
+        }
+    };
+
+    if display.show_velocities {
+        let s = scale(joints[0].1.velocity);
+        for (p, m) in joints {
+            draw_arrow(
+                context,
+                p,
+                (s * m.velocity.0, s * m.velocity.1),
+                (0.0, 0.3, 0.9),
+            )?;
+        }
+    }
+    if display.show_accelerations {
+        let s = scale(joints[0].1.acceleration);
+        for (p, m) in joints {
+            draw_arrow(
+                context,
+                p,
+                (s * m.acceleration.0, s * m.acceleration.1),
+                (0.9, 0.5, 0.0),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws the linkage in `pose` with its coupler curve from `paths`, the
+/// visible `tracers` and annotations, as seen through `view`.
+pub fn draw_four_bar_linkage(
+    context: &Context,
+    view: &View,
+    (pose, paths): (&Pose, &FourBarPaths),
+    tracers: &[Tracer],
+    display: &DisplaySettings,
+    crank: LinkMotion,
+) -> Result<(), cairo::Error> {
+    // glyphs and annotations keep their size in pixels, so everything below
+    // works on screen positions rather than a scaled cairo matrix
+    let screen = view.pose_to_screen(pose);
+
+    for link in Link::ALL {
+        let (p1, p2) = screen.link(link);
+        draw_connecting_line(context, p1, p2)?;
+    }
+
+    draw_coupler_curve(context, view, pose, paths, display.show_both_branches)?;
+    let four_bar = FourBar::from_pose(pose);
+    for tracer in tracers.iter().filter(|tracer| tracer.visible) {
+        let Some(link) = tracer.link() else {
+            continue;
+        };
+        let path = four_bar.trace(pose.crank_angle(), COUPLER_CURVE_RESOLUTION, |pose| {
+            let (start, end) = pose.link(link);
+            tracer.place(start, end)
+        });
+        draw_tracer(context, view, tracer, pose.link(link), &path)?;
+    }
+    draw_transmission_angle(
+        context,
+        &screen,
+        display.min_transmission_angle.to_radians(),
+    )?;
+
+    draw_joint(context, screen.rocker_pin)?;
+    draw_joint(context, screen.crank_pin)?;
+    draw_support(context, screen.crank_pivot)?;
+    draw_support(context, screen.rocker_pivot)?;
+
+    if display.show_velocities || display.show_accelerations {
+        if let Some(motion) = pose.motion(crank.omega, crank.alpha) {
+            draw_motion(context, view, &screen, &motion, display)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Strokes a path split into `segments`, as returned by
+/// [`FourBar::coupler_curve`].
+fn stroke_segments(
+    context: &Context,
+    view: &View,
+    segments: &[Vec<Point>],
+) -> Result<(), cairo::Error> {
+    for segment in segments {
+        let Some((&first, rest)) = segment.split_first() else {
+            continue;
+        };
+        let start = view.to_screen(first);
+        context.move_to(start.0, start.1);
+        for p in rest {
+            let p = view.to_screen(*p);
+            context.line_to(p.0, p.1);
+        }
+        context.stroke()?;
+    }
+
+    Ok(())
+}
+
+fn draw_coupler_curve(
+    context: &Context,
+    view: &View,
+    pose: &Pose,
+    paths: &FourBarPaths,
+    show_both_branches: bool,
+) -> Result<(), cairo::Error> {
+    context.save()?;
+
+    context.set_line_width(1.5 * STROKE_WIDTH);
+
+    if show_both_branches {
+        context.set_source_rgba(0.0, 0.0, 1.0, 0.4);
+        stroke_segments(context, view, &paths.other_coupler_curve)?;
+    }
+
+    context.set_source_rgba(1.0, 0.0, 0.0, 0.6);
+    stroke_segments(context, view, &paths.coupler_curve)?;
+
+    context.set_line_width(STROKE_WIDTH);
+
+    let pose = view.pose_to_screen(pose);
+
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.move_to(pose.rocker_pin.0, pose.rocker_pin.1);
+    context.line_to(pose.coupler_point.0, pose.coupler_point.1);
+    context.line_to(pose.crank_pin.0, pose.crank_pin.1);
+    context.stroke()?;
+
+    context.arc(
+        pose.coupler_point.0,
+        pose.coupler_point.1,
+        5.0,
+        0.0,
+        2.0 * std::f64::consts::PI,
+    );
+    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+
+    context.restore()
+}
+
+/// Draws whichever mechanism is shown: `linkage` if there is one, with the
+/// paths of its points taken from its revolution, otherwise the four-bar in
+/// its pose with its paths.
+pub fn draw_mechanism(
+    context: &Context,
+    view: &View,
+    four_bar: (&Pose, &FourBarPaths),
+    linkage: Option<(&Linkage, &Revolution)>,
+    tracers: &[Tracer],
+    display: &DisplaySettings,
+    crank: LinkMotion,
+) -> Result<(), cairo::Error> {
+    match linkage {
+        Some((linkage, revolution)) => draw_linkage(context, view, linkage, revolution, tracers),
+        None => draw_four_bar_linkage(context, view, four_bar, tracers, display, crank),
+    }
+}
+
+/// Draws a point of `tracer` on the link from `start` to `end`, joined to
+/// both of its ends, and the `path` it traces in the tracer's colour.
+fn draw_tracer(
+    context: &Context,
+    view: &View,
+    tracer: &Tracer,
+    (start, end): (Point, Point),
+    path: &[Vec<Point>],
+) -> Result<(), cairo::Error> {
+    let (r, g, b) = tracer.color;
+    context.save()?;
+    context.set_line_width(1.5 * STROKE_WIDTH);
+    context.set_source_rgba(r, g, b, 0.6);
+    stroke_segments(context, view, path)?;
+
+    let p = view.to_screen(tracer.place(start, end));
+    let (start, end) = (view.to_screen(start), view.to_screen(end));
+    context.set_line_width(STROKE_WIDTH);
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.move_to(start.0, start.1);
+    context.line_to(p.0, p.1);
+    context.line_to(end.0, end.1);
+    context.stroke()?;
+
+    context.arc(
+        p.0,
+        p.1,
+        TRACED_POINT_RADIUS,
+        0.0,
+        2.0 * std::f64::consts::PI,
+    );
+    context.set_source_rgba(r, g, b, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+    context.restore()
+}
+
+/// Draws a general linkage with the paths of its traced points and visible
+/// `tracers` over `revolution`, as seen through `view`.
+///
+/// The revolution is solved by the caller so that it can be kept while
+/// only the crank turns. Bodies are drawn as lines or, with three or more
+/// joints, as shaded plates; sliders as a block riding in a guide rail.
+pub fn draw_linkage(
+    context: &Context,
+    view: &View,
+    linkage: &Linkage,
+    revolution: &Revolution,
+    tracers: &[Tracer],
+) -> Result<(), cairo::Error> {
+    let screen: Vec<Point> = linkage.joints.iter().map(|&p| view.to_screen(p)).collect();
+    let owners = linkage.owners();
+    let rail_ends: Vec<usize> = linkage
+        .sliders
+        .iter()
+        .flat_map(|slider| [slider.rail.0, slider.rail.1])
+        .collect();
+    // points carried along by a single moving body
+    let traced: Vec<usize> = (0..linkage.joints.len())
+        .filter(|&joint| {
+            matches!(owners[joint].as_slice(), &[body] if body != GROUND)
+                && !rail_ends.contains(&joint)
+        })
+        .collect();
+
+    let tracers: Vec<(&Tracer, (Point, Point))> = tracers
+        .iter()
+        .filter(|tracer| tracer.visible)
+        .filter_map(|tracer| Some((tracer, tracer.ends_in(linkage)?)))
+        .collect();
+
+    context.save()?;
+    context.set_line_width(1.5 * STROKE_WIDTH);
+    context.set_source_rgba(1.0, 0.0, 0.0, 0.6);
+    for &joint in &traced {
+        let path = linkage_path(revolution, |position| Some(position.joints[joint]));
+        stroke_segments(context, view, &path)?;
+    }
+    context.restore()?;
+
+    for joints in linkage.bodies.iter().skip(1) {
+        draw_body(context, joints.iter().map(|&joint| screen[joint]))?;
+    }
+
+    for slider in &linkage.sliders {
+        let rail = (screen[slider.rail.0], screen[slider.rail.1]);
+        draw_rail(context, rail, slider.guide == GROUND)?;
+        draw_block(context, screen[slider.joint], rail)?;
+    }
+
+    for (joint, owners) in owners.iter().enumerate() {
+        if owners.len() < 2 {
+            continue;
+        }
+        if owners.contains(&GROUND) {
+            draw_support(context, screen[joint])?;
+        } else {
+            draw_joint(context, screen[joint])?;
+        }
+    }
+
+    for &joint in &traced {
+        draw_traced_point(context, screen[joint])?;
+    }
+
+    for (tracer, link) in tracers {
+        let path = linkage_path(revolution, |position| tracer.on_linkage(position));
+        draw_tracer(context, view, tracer, link, &path)?;
+    }
+
+    Ok(())
+}
+
+/// A line between two joints, or a shaded plate between three or more.
+fn draw_body(context: &Context, joints: impl Iterator<Item = Point>) -> Result<(), cairo::Error> {
+    let joints: Vec<Point> = joints.collect();
+    if joints.len() < 2 {
+        return Ok(());
+    }
+
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    context.move_to(joints[0].0, joints[0].1);
+    for p in &joints[1..] {
+        context.line_to(p.0, p.1);
+    }
+    if joints.len() > 2 {
+        context.close_path();
+        context.set_source_rgba(0.5, 0.5, 0.5, 0.2);
+        context.fill_preserve()?;
+    }
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+    context.restore()
+}
+
+/// Unit vector from `a` towards `b` and the one to its left on screen, or
+/// `None` if the points coincide.
+fn rail_axes(a: Point, b: Point) -> Option<(Point, Point)> {
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+    if length < 1e-9 {
+        return None;
+    }
+    let along = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+    Some((along, (along.1, -along.0)))
+}
+
+/// The two sides of a guide rail between its ends, hatched on the outside
+/// of one side if it is fixed to the ground.
+fn draw_rail(context: &Context, rail: (Point, Point), grounded: bool) -> Result<(), cairo::Error> {
+    let Some((along, across)) = rail_axes(rail.0, rail.1) else {
+        return Ok(());
+    };
+    let (a, b) = rail;
+    let half = 0.5 * BLOCK_WIDTH + STROKE_WIDTH;
+
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    for side in [-1.0, 1.0] {
+        let offset = (side * half * across.0, side * half * across.1);
+        context.move_to(a.0 + offset.0, a.1 + offset.1);
+        context.line_to(b.0 + offset.0, b.1 + offset.1);
+    }
+
+    if grounded {
+        let length = (b.0 - a.0).hypot(b.1 - a.1);
+        let count = (length / RAIL_HATCH_SPACING) as usize;
+        for i in 0..=count {
+            let t = i as f64 * RAIL_HATCH_SPACING;
+            let start = (
+                a.0 + t * along.0 - half * across.0,
+                a.1 + t * along.1 - half * across.1,
+            );
+            context.move_to(start.0, start.1);
+            context.rel_line_to(
+                -RAIL_HATCH_LENGTH * (across.0 + 0.5 * along.0),
+                -RAIL_HATCH_LENGTH * (across.1 + 0.5 * along.1),
+            );
+        }
+    }
+
+    context.stroke()?;
+    context.restore()
+}
+
+/// A slider block centred on `p`, lying along `rail`.
+fn draw_block(context: &Context, p: Point, rail: (Point, Point)) -> Result<(), cairo::Error> {
+    let Some((along, across)) = rail_axes(rail.0, rail.1) else {
+        return Ok(());
+    };
+    let corner = |l: f64, w: f64| {
+        (
+            p.0 + 0.5 * (l * BLOCK_LENGTH * along.0 + w * BLOCK_WIDTH * across.0),
+            p.1 + 0.5 * (l * BLOCK_LENGTH * along.1 + w * BLOCK_WIDTH * across.1),
+        )
+    };
+
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    let start = corner(-1.0, -1.0);
+    context.move_to(start.0, start.1);
+    for (l, w) in [(1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+        let c = corner(l, w);
+        context.line_to(c.0, c.1);
+    }
+    context.close_path();
+    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+    context.restore()
+}
+
+fn draw_traced_point(context: &Context, p: Point) -> Result<(), cairo::Error> {
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    context.arc(
+        p.0,
+        p.1,
+        TRACED_POINT_RADIUS,
+        0.0,
+        2.0 * std::f64::consts::PI,
+    );
+    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+    context.fill_preserve()?;
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.stroke()?;
+    context.restore()
+}
+
+/// Marks the precision points of a path synthesis with numbered crosses,
+/// in the order the coupler point should pass them.
+pub fn draw_precision_points(
+    context: &Context,
+    view: &View,
+    points: &[Point],
+) -> Result<(), cairo::Error> {
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    context.set_source_rgba(0.0, 0.3, 0.8, 1.0);
+    context.set_font_size(14.0);
+    for (number, &p) in (1..).zip(points) {
+        let p = view.to_screen(p);
+        context.move_to(p.0 - PRECISION_POINT_SIZE, p.1 - PRECISION_POINT_SIZE);
+        context.line_to(p.0 + PRECISION_POINT_SIZE, p.1 + PRECISION_POINT_SIZE);
+        context.move_to(p.0 - PRECISION_POINT_SIZE, p.1 + PRECISION_POINT_SIZE);
+        context.line_to(p.0 + PRECISION_POINT_SIZE, p.1 - PRECISION_POINT_SIZE);
+        context.stroke()?;
+        context.move_to(p.0 + PRECISION_POINT_SIZE, p.1 - PRECISION_POINT_SIZE);
+        context.show_text(&number.to_string())?;
+    }
+    context.restore()
+}
+
+/// Draws the coupler positions of a motion synthesis, numbered, and the
+/// construction of the four-bar through them with the free `choices`.
+///
+/// Each position shows its coupler point with an arrow in its direction
+/// and the coupler triangle to the two pins. The positions of each pin are
+/// joined, with the perpendicular bisectors of the joins leading to its
+/// ground pivot; for three positions the pole triangle is drawn as well.
+/// The pins in the first position and, for two positions, the ground
+/// pivots are the handles picking the free choices.
+pub fn draw_coupler_positions(
+    context: &Context,
+    view: &View,
+    positions: &[CouplerPosition],
+    choices: &MotionChoices,
+) -> Result<(), cairo::Error> {
+    context.save()?;
+    context.set_line_width(STROKE_WIDTH);
+    context.set_font_size(14.0);
+
+    for (number, position) in (1..).zip(positions) {
+        let p = view.to_screen(position.point);
+        let [crank_pin, rocker_pin] = choices
+            .moving_pivots
+            .map(|pin| view.to_screen(position.place(pin)));
+        context.set_source_rgba(0.0, 0.0, 0.0, 0.3);
+        context.move_to(p.0, p.1);
+        context.line_to(crank_pin.0, crank_pin.1);
+        context.line_to(rocker_pin.0, rocker_pin.1);
+        context.close_path();
+        context.stroke()?;
+
+        let (sin, cos) = position.angle.sin_cos();
+        let arrow = (POSITION_ARROW_LENGTH * cos, -POSITION_ARROW_LENGTH * sin);
+        draw_arrow(context, p, arrow, (0.0, 0.5, 0.3))?;
+        draw_traced_point(context, p)?;
+        context.set_source_rgba(0.0, 0.5, 0.3, 1.0);
+        context.move_to(p.0 - 2.0 * TRACED_POINT_RADIUS, p.1 - TRACED_POINT_RADIUS);
+        context.show_text(&number.to_string())?;
+    }
+
+    for (dyad, color) in DYAD_COLORS.iter().enumerate() {
+        let (r, g, b) = *color;
+        let Some(first) = positions.first() else {
+            break;
+        };
+        let construction = dyad_construction(
+            positions,
+            choices.moving_pivots[dyad],
+            choices.bisector_offsets[dyad],
+        );
+        if let Some(construction) = construction {
+            context.set_source_rgba(r, g, b, 0.8);
+            context.set_dash(&[6.0, 4.0], 0.0);
+            for pair in construction.moving_pivots.windows(2) {
+                let (start, end) = (view.to_screen(pair[0]), view.to_screen(pair[1]));
+                context.move_to(start.0, start.1);
+                context.line_to(end.0, end.1);
+            }
+            context.stroke()?;
+
+            // each bisector from past its middle to past the ground pivot
+            let ground_pivot = view.to_screen(construction.ground_pivot);
+            context.set_dash(&[2.0, 4.0], 0.0);
+            for &(middle, direction) in &construction.bisectors {
+                let middle = view.to_screen(middle);
+                let direction = (direction.0, -direction.1);
+                let reach = (ground_pivot.0 - middle.0) * direction.0
+                    + (ground_pivot.1 - middle.1) * direction.1;
+                let (from, to) = (
+                    reach.min(0.0) - CONSTRUCTION_LINE_OVERHANG,
+                    reach.max(0.0) + CONSTRUCTION_LINE_OVERHANG,
+                );
+                context.move_to(middle.0 + from * direction.0, middle.1 + from * direction.1);
+                context.line_to(middle.0 + to * direction.0, middle.1 + to * direction.1);
+            }
+            context.stroke()?;
+            context.set_dash(&[], 0.0);
+
+            context.move_to(ground_pivot.0 + JOINT_RADIUS, ground_pivot.1);
+            context.arc(
+                ground_pivot.0,
+                ground_pivot.1,
+                JOINT_RADIUS,
+                0.0,
+                2.0 * std::f64::consts::PI,
+            );
+            context.move_to(ground_pivot.0 - JOINT_RADIUS, ground_pivot.1);
+            context.line_to(ground_pivot.0 + JOINT_RADIUS, ground_pivot.1);
+            context.move_to(ground_pivot.0, ground_pivot.1 - JOINT_RADIUS);
+            context.line_to(ground_pivot.0, ground_pivot.1 + JOINT_RADIUS);
+            context.stroke()?;
+        }
+
+        let pin = view.to_screen(first.place(choices.moving_pivots[dyad]));
+        context.arc(
+            pin.0,
+            pin.1,
+            TRACED_POINT_RADIUS,
+            0.0,
+            2.0 * std::f64::consts::PI,
+        );
+        context.set_source_rgba(r, g, b, 1.0);
+        context.fill_preserve()?;
+        context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+        context.stroke()?;
+    }
+
+    if let Ok(three) = <&[CouplerPosition; 3]>::try_from(positions) {
+        if let Some(poles) = pole_triangle(three) {
+            let poles = poles.map(|pole| view.to_screen(pole));
+            context.set_source_rgba(0.6, 0.0, 0.6, 0.8);
+            context.move_to(poles[0].0, poles[0].1);
+            context.line_to(poles[1].0, poles[1].1);
+            context.line_to(poles[2].0, poles[2].1);
+            context.close_path();
+            context.stroke()?;
+            for (pole, name) in poles.iter().zip(["P12", "P13", "P23"]) {
+                context.arc(pole.0, pole.1, 3.0, 0.0, 2.0 * std::f64::consts::PI);
+                context.fill()?;
+                context.move_to(pole.0 + 6.0, pole.1 - 6.0);
+                context.show_text(name)?;
+            }
+        }
+    }
+    context.restore()
+}
+
+/// The path of `point` over a `revolution` of a linkage, split wherever the
+/// linkage cannot be assembled.
+fn linkage_path(
+    revolution: &Revolution,
+    point: impl Fn(&Linkage) -> Option<Point>,
+) -> Vec<Vec<Point>> {
+    let mut segments = Vec::new();
+    let mut current = Vec::new();
+    for (_, solved) in revolution {
+        match solved.as_ref().ok().and_then(&point) {
+            Some(p) => current.push(p),
+            None => {
+                if !current.is_empty() {
+                    segments.push(std::mem::take(&mut current));
+                }
+            }
+        }
+    }
+    if !current.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
@@ -0,0 +1,183 @@
+//! Side panel for entering exact joint positions and link lengths.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{Grid, Label, SpinButton};
+use kinematicsolver::{Joint, Link, Point, Pose};
+
+/// Largest coordinate or length that can be entered, in millimetres.
+pub const MAX_DIMENSION: f64 = 100_000.0;
+
+/// Smallest link length that can be entered, in millimetres: a link of no
+/// length has no direction to turn.
+pub const MIN_LENGTH: f64 = 0.01;
+
+/// A change made in the editor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// Move a joint to a new position.
+    Joint(Joint, Point),
+    /// Resize a link, see [`Pose::with_length`].
+    Length(Link, f64),
+}
+
+fn joint_name(joint: Joint) -> &'static str {
+    match joint {
+        Joint::CrankPivot => "Crank pivot",
+        Joint::CrankPin => "Crank pin",
+        Joint::RockerPin => "Rocker pin",
+        Joint::RockerPivot => "Rocker pivot",
+        Joint::CouplerPoint => "Coupler point",
+    }
+}
+
+pub fn link_name(link: Link) -> &'static str {
+    match link {
+        Link::Ground => "Ground",
+        Link::Crank => "Crank",
+        Link::Coupler => "Coupler",
+        Link::Rocker => "Rocker",
+    }
+}
+
+pub fn spin_button(min: f64) -> SpinButton {
+    let spin = SpinButton::with_range(min, MAX_DIMENSION, 1.0);
+    spin.set_digits(2);
+    spin.set_width_chars(9);
+    spin
+}
+
+/// Calls `on_change` whenever the value of `spin` changes, except when GTK
+/// merely takes up the rounded text it shows, as it does when the spin
+/// button is activated or loses focus: that is no edit, and would move the
+/// model by the rounding. Text the user typed is always taken, even if it
+/// is what was shown.
+pub fn connect_value_edited(spin: &SpinButton, on_change: impl Fn(&SpinButton) + 'static) {
+    let typed = Rc::new(Cell::new(false));
+    spin.connect_changed({
+        let typed = typed.clone();
+        move |spin| {
+            // GTK showing the value writes exactly this text
+            if spin.text() != shown(spin, spin.value()) {
+                typed.set(true);
+            }
+        }
+    });
+    let previous = Cell::new(spin.value());
+    spin.connect_value_changed(move |spin| {
+        let previous = previous.replace(spin.value());
+        if typed.replace(false) || shown(spin, spin.value()) != shown(spin, previous) {
+            on_change(spin);
+        }
+    });
+}
+
+/// The text `spin` shows for `value`.
+fn shown(spin: &SpinButton, value: f64) -> String {
+    format!("{:.*}", spin.digits() as usize, value)
+}
+
+/// Spin buttons for every joint's position and every link's length.
+pub struct PropertyEditor {
+    grid: Grid,
+    joints: Vec<(Joint, SpinButton, SpinButton)>,
+    links: Vec<(Link, SpinButton)>,
+    /// Set while [`PropertyEditor::update`] writes to the spin buttons, so
+    /// that showing the model is not mistaken for an edit.
+    updating: Rc<Cell<bool>>,
+}
+
+impl PropertyEditor {
+    /// Builds the panel, calling `on_edit` whenever the user changes a value.
+    pub fn new(on_edit: impl Fn(Edit) + 'static) -> Self {
+        let on_edit = Rc::new(on_edit);
+        let updating = Rc::new(Cell::new(false));
+        let grid = Grid::builder().row_spacing(6).column_spacing(6).build();
+
+        grid.attach(&Label::new(Some("x [mm]")), 1, 0, 1, 1);
+        grid.attach(&Label::new(Some("y [mm]")), 2, 0, 1, 1);
+        let mut joints = Vec::new();
+        for (row, joint) in (1..).zip(Joint::ALL) {
+            let x = spin_button(-MAX_DIMENSION);
+            let y = spin_button(-MAX_DIMENSION);
+            for spin in [&x, &y] {
+                let (x, y) = (x.clone(), y.clone());
+                let on_edit = on_edit.clone();
+                let updating = updating.clone();
+                connect_value_edited(spin, move |_| {
+                    if !updating.get() {
+                        on_edit(Edit::Joint(joint, (x.value(), y.value())));
+                    }
+                });
+            }
+            let label = Label::builder()
+                .label(joint_name(joint))
+                .xalign(0.0)
+                .build();
+            grid.attach(&label, 0, row, 1, 1);
+            grid.attach(&x, 1, row, 1, 1);
+            grid.attach(&y, 2, row, 1, 1);
+            joints.push((joint, x, y));
+        }
+
+        let first_link_row = Joint::ALL.len() as i32 + 2;
+        grid.attach(
+            &Label::new(Some("length [mm]")),
+            1,
+            first_link_row - 1,
+            1,
+            1,
+        );
+        let mut links = Vec::new();
+        for (row, link) in (first_link_row..).zip(Link::ALL) {
+            let spin = spin_button(MIN_LENGTH);
+            let on_edit = on_edit.clone();
+            let updating = updating.clone();
+            connect_value_edited(&spin, move |spin| {
+                if !updating.get() {
+                    on_edit(Edit::Length(link, spin.value()));
+                }
+            });
+            let label = Label::builder().label(link_name(link)).xalign(0.0).build();
+            grid.attach(&label, 0, row, 1, 1);
+            grid.attach(&spin, 1, row, 1, 1);
+            links.push((link, spin));
+        }
+
+        PropertyEditor {
+            grid,
+            joints,
+            links,
+            updating,
+        }
+    }
+
+    pub fn widget(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Shows the dimensions of `pose`.
+    ///
+    /// Spin buttons that already show the right value are left alone, so
+    /// that calling this every frame does not disturb typing.
+    pub fn update(&self, pose: &Pose) {
+        let set = |spin: &SpinButton, value: f64| {
+            if (spin.value() - value).abs() > 1e-9 {
+                spin.set_value(value);
+            }
+        };
+
+        self.updating.set(true);
+        for (joint, x, y) in &self.joints {
+            let p = pose.joint(*joint);
+            set(x, p.0);
+            set(y, p.1);
+        }
+        for (link, spin) in &self.links {
+            set(spin, pose.length(*link));
+        }
+        self.updating.set(false);
+    }
+}
@@ -0,0 +1,91 @@
+//! Export of the mechanism drawing to image files.
+
+use std::fs::File;
+use std::path::Path;
+
+use cairo::{Context, ImageSurface, IoError, PdfSurface, SvgSurface};
+use kinematicsolver::{Design, FourBar, LinkMotion};
+
+use super::draw::{draw_mechanism, FourBarPaths, LINKAGE_PATH_RESOLUTION};
+use super::view::View;
+
+/// Size of the exported page in pixels or points.
+const PAGE_SIZE: f64 = 1000.0;
+
+/// File formats the drawing can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Svg,
+    Pdf,
+    Png,
+}
+
+impl Format {
+    /// Picks the format from the extension of `path`, ignoring case.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "svg" => Some(Format::Svg),
+            "pdf" => Some(Format::Pdf),
+            "png" => Some(Format::Png),
+            _ => None,
+        }
+    }
+}
+
+/// Renders the mechanism of `design`, with its coupler curve, tracers and
+/// annotations, to a file at `path`, zoomed to fill the page.
+pub fn export_drawing(
+    path: &Path,
+    format: Format,
+    design: &Design,
+    crank: LinkMotion,
+) -> Result<(), IoError> {
+    let pose = design.pose();
+    let paths = FourBarPaths::new(FourBar::from_pose(&pose));
+    let linkage = design.linkage.as_ref();
+    let revolution = linkage.map(|linkage| linkage.revolution(LINKAGE_PATH_RESOLUTION));
+    let drawn = linkage.zip(revolution.as_deref());
+    let tracers = &design.tracers;
+    let view = View::fit_mechanism(&pose, linkage, PAGE_SIZE, PAGE_SIZE);
+    let surface: cairo::Surface = match format {
+        Format::Svg => (*SvgSurface::new(PAGE_SIZE, PAGE_SIZE, Some(path))?).clone(),
+        Format::Pdf => (*PdfSurface::new(PAGE_SIZE, PAGE_SIZE, path)?).clone(),
+        Format::Png => {
+            let size = PAGE_SIZE as i32;
+            let surface = ImageSurface::create(cairo::Format::ARgb32, size, size)?;
+            let context = Context::new(&surface)?;
+            // unlike the vector formats, a bitmap has no page to show through
+            context.set_source_rgb(1.0, 1.0, 1.0);
+            context.paint()?;
+            draw_mechanism(
+                &context,
+                &view,
+                (&pose, &paths),
+                drawn,
+                tracers,
+                &design.display,
+                crank,
+            )?;
+            drop(context);
+
+            let mut file = File::create(path)?;
+            return surface.write_to_png(&mut file);
+        }
+    };
+
+    let context = Context::new(&surface)?;
+    draw_mechanism(
+        &context,
+        &view,
+        (&pose, &paths),
+        drawn,
+        tracers,
+        &design.display,
+        crank,
+    )?;
+    drop(context);
+
+    surface.finish();
+    Ok(surface.status()?)
+}
@@ -0,0 +1,95 @@
+//! File dialogs for opening, saving and exporting designs.
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use gtk::prelude::*;
+use gtk::{
+    ButtonsType, FileChooserAction, FileChooserNative, FileFilter, MessageDialog, MessageType,
+    ResponseType, Window,
+};
+
+/// A type of file offered by [`choose_file`].
+pub struct FileKind {
+    pub name: &'static str,
+    pub pattern: &'static str,
+    /// Suggested name when saving.
+    pub default_name: &'static str,
+}
+
+pub const DESIGN: FileKind = FileKind {
+    name: "Mechanism designs",
+    pattern: "*.json",
+    default_name: "mechanism.json",
+};
+
+pub const CSV: FileKind = FileKind {
+    name: "CSV files",
+    pattern: "*.csv",
+    default_name: "coupler-curve.csv",
+};
+
+pub const SVG: FileKind = FileKind {
+    name: "SVG images",
+    pattern: "*.svg",
+    default_name: "mechanism.svg",
+};
+
+pub const PDF: FileKind = FileKind {
+    name: "PDF documents",
+    pattern: "*.pdf",
+    default_name: "mechanism.pdf",
+};
+
+/// Asks the user for a file of the given kind and calls `on_chosen` with its
+/// path, unless the dialog is cancelled.
+pub fn choose_file(
+    parent: &impl IsA<Window>,
+    title: &str,
+    action: FileChooserAction,
+    kind: &FileKind,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    let accept = match action {
+        FileChooserAction::Save => "_Save",
+        _ => "_Open",
+    };
+    let dialog = FileChooserNative::new(Some(title), Some(parent), action, Some(accept), None);
+
+    let filter = FileFilter::new();
+    filter.set_name(Some(kind.name));
+    filter.add_pattern(kind.pattern);
+    dialog.add_filter(&filter);
+    if action == FileChooserAction::Save {
+        dialog.set_current_name(kind.default_name);
+    }
+
+    // GTK holds no reference to a native dialog, so this one is kept alive
+    // by its own handler until it responds
+    let keep_alive = RefCell::new(Some(dialog.clone()));
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                on_chosen(path);
+            }
+        }
+        dialog.destroy();
+        keep_alive.take();
+    });
+    dialog.show();
+}
+
+/// Shows `err` in a modal message box on top of `parent`.
+pub fn show_error(parent: &impl IsA<Window>, message: &str, err: &dyn Display) {
+    let dialog = MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(MessageType::Error)
+        .buttons(ButtonsType::Close)
+        .text(message)
+        .secondary_text(err.to_string())
+        .build();
+    dialog.connect_response(|dialog, _| dialog.destroy());
+    dialog.show();
+}
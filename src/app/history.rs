@@ -0,0 +1,78 @@
+//! Undo and redo of changes to the mechanism.
+
+use kinematicsolver::{Design, Linkage, Pose, Tracer};
+
+/// Oldest commands are forgotten beyond this many.
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// A change that can be undone, with the state before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// A joint drag, a numeric edit or a switch of branches.
+    Edit { before: Pose, after: Pose },
+    /// A joint drag on a general linkage.
+    EditLinkage { before: Linkage, after: Linkage },
+    /// Adding, removing or changing a tracer.
+    EditTracers {
+        before: Vec<Tracer>,
+        after: Vec<Tracer>,
+    },
+    /// Opening a design file or starting from a template, which also
+    /// replaces the display settings.
+    Load {
+        before: Box<Design>,
+        after: Box<Design>,
+    },
+}
+
+/// The commands that can be undone and redone, most recent last.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+}
+
+impl History {
+    /// Records a command that has just been carried out.
+    ///
+    /// Edits that did not change anything are ignored.
+    pub fn push(&mut self, command: Command) {
+        let unchanged = match &command {
+            Command::Edit { before, after } => before == after,
+            Command::EditLinkage { before, after } => before == after,
+            Command::EditTracers { before, after } => before == after,
+            Command::Load { .. } => false,
+        };
+        if unchanged {
+            return;
+        }
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.undo.push(command);
+        self.redo.clear();
+    }
+
+    /// Takes the most recent command for the caller to revert.
+    pub fn undo(&mut self) -> Option<Command> {
+        let command = self.undo.pop()?;
+        self.redo.push(command.clone());
+        Some(command)
+    }
+
+    /// Takes the most recently undone command for the caller to carry out
+    /// again.
+    pub fn redo(&mut self) -> Option<Command> {
+        let command = self.redo.pop()?;
+        self.undo.push(command.clone());
+        Some(command)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
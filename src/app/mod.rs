@@ -0,0 +1,12 @@
+//! Parts of the GTK application that live outside of `main.rs`.
+
+pub mod cli;
+pub mod draw;
+pub mod editor;
+pub mod export;
+pub mod file;
+pub mod history;
+pub mod plots;
+pub mod state;
+pub mod tracers;
+pub mod view;
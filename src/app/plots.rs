@@ -0,0 +1,188 @@
+//! Plots of the link angles and the coupler point over one crank revolution.
+
+use std::f64::consts::PI;
+
+use cairo::Context;
+use kinematicsolver::Pose;
+
+use super::draw::{FourBarPaths, STROKE_WIDTH};
+
+const MARGIN: f64 = 40.0;
+const FONT_SIZE: f64 = 12.0;
+
+struct Series {
+    label: &'static str,
+    color: (f64, f64, f64),
+    values: Vec<Option<f64>>,
+}
+
+/// Removes the jumps of ±360° where an angle wraps around.
+fn unwrap_angles(values: &mut [Option<f64>]) {
+    let mut previous: Option<f64> = None;
+    for value in values.iter_mut() {
+        match (value.as_mut(), previous) {
+            (Some(v), Some(p)) => {
+                *v -= ((*v - p) / 360.0).round() * 360.0;
+                previous = Some(*v);
+            }
+            (Some(v), None) => previous = Some(*v),
+            (None, _) => previous = None,
+        }
+    }
+}
+
+/// Draws rocker and coupler angle and the coupler point position against the
+/// crank angle, over the revolution in `paths` and with a cursor at the
+/// crank angle of `pose`.
+pub fn draw_plots(
+    context: &Context,
+    width: f64,
+    height: f64,
+    pose: &Pose,
+    paths: &FourBarPaths,
+) -> Result<(), cairo::Error> {
+    let samples = &paths.poses;
+
+    let mut rocker: Vec<_> = samples
+        .iter()
+        .map(|p| p.map(|p| p.rocker_angle().to_degrees()))
+        .collect();
+    let mut coupler: Vec<_> = samples
+        .iter()
+        .map(|p| p.map(|p| p.coupler_angle().to_degrees()))
+        .collect();
+    unwrap_angles(&mut rocker);
+    unwrap_angles(&mut coupler);
+
+    let cursor = pose.crank_angle().rem_euclid(2.0 * PI) / (2.0 * PI);
+    let chart_height = height / 2.0;
+
+    draw_chart(
+        context,
+        (0.0, 0.0, width, chart_height),
+        "Link angles [°]",
+        &[
+            Series {
+                label: "rocker",
+                color: (0.0, 0.4, 0.8),
+                values: rocker,
+            },
+            Series {
+                label: "coupler",
+                color: (0.0, 0.6, 0.0),
+                values: coupler,
+            },
+        ],
+        cursor,
+    )?;
+    draw_chart(
+        context,
+        (0.0, chart_height, width, chart_height),
+        "Coupler point [mm]",
+        &[
+            Series {
+                label: "x",
+                color: (0.8, 0.0, 0.0),
+                values: samples
+                    .iter()
+                    .map(|p| p.map(|p| p.coupler_point.0))
+                    .collect(),
+            },
+            Series {
+                label: "y",
+                color: (0.6, 0.0, 0.6),
+                values: samples
+                    .iter()
+                    .map(|p| p.map(|p| p.coupler_point.1))
+                    .collect(),
+            },
+        ],
+        cursor,
+    )
+}
+
+/// Draws `series` against a crank angle axis from 0° to 360° into `area`
+/// (x, y, width, height). `cursor` is the fraction of a revolution to mark.
+fn draw_chart(
+    context: &Context,
+    area: (f64, f64, f64, f64),
+    title: &str,
+    series: &[Series],
+    cursor: f64,
+) -> Result<(), cairo::Error> {
+    context.save()?;
+
+    let (left, top) = (area.0 + MARGIN, area.1 + MARGIN);
+    let (width, height) = (area.2 - 2.0 * MARGIN, area.3 - 2.0 * MARGIN);
+
+    let (min, max) = series
+        .iter()
+        .flat_map(|s| s.values.iter().flatten())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    let (min, max) = if min > max {
+        (0.0, 1.0)
+    } else if max - min < 1e-9 {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    };
+
+    context.set_font_size(FONT_SIZE);
+    context.set_line_width(STROKE_WIDTH);
+
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.8);
+    context.move_to(left, top - 0.75 * FONT_SIZE);
+    context.show_text(title)?;
+
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    context.rectangle(left, top, width, height);
+    context.stroke()?;
+
+    context.move_to(left + 2.0, top + FONT_SIZE);
+    context.show_text(&format!("{max:.1}"))?;
+    context.move_to(left + 2.0, top + height - 4.0);
+    context.show_text(&format!("{min:.1}"))?;
+    context.move_to(left, top + height + FONT_SIZE + 2.0);
+    context.show_text("0°")?;
+    context.move_to(
+        left + width - 3.0 * FONT_SIZE,
+        top + height + FONT_SIZE + 2.0,
+    );
+    context.show_text("360°")?;
+
+    for (i, s) in series.iter().enumerate() {
+        context.set_source_rgb(s.color.0, s.color.1, s.color.2);
+
+        let steps = (s.values.len() - 1).max(1) as f64;
+        let mut pen_down = false;
+        for (j, value) in s.values.iter().enumerate() {
+            match value {
+                Some(v) => {
+                    let x = left + width * j as f64 / steps;
+                    let y = top + height * (max - v) / (max - min);
+                    if pen_down {
+                        context.line_to(x, y);
+                    } else {
+                        context.move_to(x, y);
+                    }
+                    pen_down = true;
+                }
+                None => pen_down = false,
+            }
+        }
+        context.stroke()?;
+
+        context.move_to(left + width - 80.0, top + (i as f64 + 1.5) * FONT_SIZE);
+        context.show_text(s.label)?;
+    }
+
+    context.set_source_rgba(0.0, 0.0, 0.0, 0.8);
+    context.set_dash(&[4.0, 4.0], 0.0);
+    context.move_to(left + width * cursor, top);
+    context.line_to(left + width * cursor, top + height);
+    context.stroke()?;
+
+    context.restore()
+}
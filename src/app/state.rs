@@ -0,0 +1,353 @@
+//! The mechanism and editing state of one window.
+//!
+//! Every window owns an `Rc<RefCell<AppState>>` shared by its callbacks, so
+//! several windows can edit separate mechanisms side by side. Borrows must
+//! end before calling into GTK, as setting a widget's value may run other
+//! callbacks of the same window.
+
+use std::f64::consts::TAU;
+use std::path::PathBuf;
+
+use kinematicsolver::geometry::length;
+use kinematicsolver::synthesis::dyad_construction;
+use kinematicsolver::{
+    templates, CouplerPosition, Design, DisplaySettings, FourBar, Joint, LinkMotion, Linkage,
+    MotionChoices, Point, Pose, SolveError, Tracer,
+};
+
+use super::draw::{FourBarPaths, JOINT_RADIUS, LINKAGE_PATH_RESOLUTION, POSITION_ARROW_LENGTH};
+use super::history::History;
+use super::view::View;
+
+/// What clicking and dragging in the drawing does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragMode {
+    /// The joint moves alone, changing the lengths of its links.
+    EditGeometry,
+    /// The mechanism turns so that the joint follows the pointer, keeping
+    /// all link lengths. Ground pivots cannot be dragged.
+    Move,
+    /// Clicking adds a precision point for path synthesis and dragging
+    /// moves one. The mechanism stays as it is.
+    PlacePoints,
+    /// Clicking adds a coupler position for motion synthesis, dragging
+    /// turns it, and the position, its direction and the free choices of
+    /// the construction can be dragged. The mechanism stays as it is.
+    PlacePositions,
+}
+
+impl DragMode {
+    pub const ALL: [DragMode; 4] = [
+        DragMode::EditGeometry,
+        DragMode::Move,
+        DragMode::PlacePoints,
+        DragMode::PlacePositions,
+    ];
+}
+
+/// What is being dragged in [`DragMode::PlacePositions`], with where the
+/// pointer or the dragged point was when the drag started.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PositionDrag {
+    /// The coupler point of a position.
+    Move(usize, Point),
+    /// The direction of a position, which points at the pointer.
+    Turn(usize, Point),
+    /// The crank pin (0) or rocker pin (1) in the first position.
+    MovingPivot(usize, Point),
+    /// The ground pivot of the crank (0) or rocker (1), which stays on its
+    /// perpendicular bisector.
+    GroundPivot(usize, Point),
+}
+
+/// A running animation.
+///
+/// Every frame is solved from the same dimensions at an absolute crank
+/// angle, so the linkage is always exactly where the angle says and cannot
+/// drift off its coupler curve however long it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    /// The pose the animation started from, restored when it stops.
+    pub reference: Pose,
+    /// Dimensions of `reference`, extracted once when the animation starts.
+    pub four_bar: FourBar,
+    /// The general linkage the animation started from, restored when it
+    /// stops. Its dimensions are used for every frame, like `four_bar`.
+    pub linkage: Option<Linkage>,
+    /// Crank angle of the frame currently shown, in radians.
+    pub crank_angle: f64,
+    /// 1 or -1, flipped when the animation turns around at a dead point.
+    pub direction: f64,
+    /// Frame clock time of the last frame, in microseconds.
+    pub last_frame: Option<i64>,
+}
+
+impl Animation {
+    pub fn new(reference: Pose, linkage: Option<Linkage>) -> Self {
+        let crank_angle = match &linkage {
+            Some(linkage) => linkage.crank_angles().first().copied().unwrap_or(0.0),
+            None => reference.crank_angle(),
+        };
+        Animation {
+            reference,
+            four_bar: FourBar::from_pose(&reference),
+            linkage,
+            crank_angle,
+            direction: 1.0,
+            last_frame: None,
+        }
+    }
+}
+
+/// A revolution of the general linkage to draw the paths of its points
+/// from, kept while only its crank turns.
+#[derive(Debug, Default)]
+pub struct LinkagePaths {
+    /// The linkage as restored after stopping the animation, whose
+    /// dimensions `revolution` was solved with.
+    linkage: Option<Linkage>,
+    /// See [`Linkage::revolution`]; empty without a general linkage.
+    pub revolution: Vec<(f64, Result<Linkage, SolveError>)>,
+}
+
+#[derive(Debug)]
+pub struct AppState {
+    /// The mechanism as currently shown.
+    pub pose: Pose,
+    /// The general linkage as currently shown, when the mechanism is not a
+    /// four-bar. It then takes the place of `pose`.
+    pub linkage: Option<Linkage>,
+    /// Points traced besides the coupler point, on the links of `linkage`
+    /// if there is one and of the four-bar otherwise.
+    pub tracers: Vec<Tracer>,
+    /// Brought up to date by [`AppState::update_linkage_paths`].
+    pub linkage_paths: LinkagePaths,
+    /// The paths of the four-bar in `pose`, brought up to date by
+    /// [`AppState::update_four_bar_paths`].
+    pub four_bar_paths: FourBarPaths,
+    /// The joint being dragged and where it was when the drag started.
+    pub selected_joint: Option<(Joint, Point)>,
+    pub animation: Option<Animation>,
+    pub display: DisplaySettings,
+    pub crank_input: LinkMotion,
+    /// Animation speed of the crank, in revolutions per minute.
+    pub speed_rpm: f64,
+    /// Holds the animation at its current frame.
+    pub paused: bool,
+    /// Turns the crank clockwise instead of counter-clockwise.
+    pub reverse: bool,
+    /// Where the design was last opened from or saved to.
+    pub current_file: Option<PathBuf>,
+    pub history: History,
+    /// The pose when the current joint drag started.
+    pub drag_start: Option<Pose>,
+    /// The joint of the general linkage being dragged, where it was and the
+    /// linkage when the drag started.
+    pub linkage_drag: Option<(usize, Point, Linkage)>,
+    pub drag_mode: DragMode,
+    /// Points the coupler curve should pass through, in order, for path
+    /// synthesis. They are not part of the design.
+    pub precision_points: Vec<Point>,
+    /// The precision point being dragged and where it was when the drag
+    /// started.
+    pub point_drag: Option<(usize, Point)>,
+    /// Positions the coupler should take up, in order, for motion
+    /// synthesis. Like the precision points, they are not part of the
+    /// design.
+    pub coupler_positions: Vec<CouplerPosition>,
+    /// The free choices of the motion synthesis construction.
+    pub motion_choices: MotionChoices,
+    pub position_drag: Option<PositionDrag>,
+    pub view: View,
+    /// Last pointer position over the drawing area, where the wheel zooms.
+    pub pointer: Point,
+    /// Origin of the view when the current middle-button pan started.
+    pub pan_start: Point,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        let pose = templates::four_bar();
+        AppState {
+            pose,
+            linkage: None,
+            tracers: Vec::new(),
+            linkage_paths: LinkagePaths::default(),
+            four_bar_paths: FourBarPaths::new(FourBar::from_pose(&pose)),
+            selected_joint: None,
+            animation: None,
+            display: DisplaySettings::default(),
+            crank_input: LinkMotion {
+                omega: 1.0,
+                alpha: 0.0,
+            },
+            speed_rpm: 10.0,
+            paused: false,
+            reverse: false,
+            current_file: None,
+            history: History::default(),
+            drag_start: None,
+            linkage_drag: None,
+            drag_mode: DragMode::EditGeometry,
+            precision_points: Vec::new(),
+            point_drag: None,
+            coupler_positions: Vec::new(),
+            motion_choices: MotionChoices::default(),
+            position_drag: None,
+            view: View::default(),
+            pointer: (0.0, 0.0),
+            pan_start: (0.0, 0.0),
+        }
+    }
+}
+
+impl AppState {
+    /// The pose as it would be restored after stopping the animation.
+    pub fn reference_pose(&self) -> Pose {
+        self.animation
+            .as_ref()
+            .map_or(self.pose, |animation| animation.reference)
+    }
+
+    /// The general linkage as it would be restored after stopping the
+    /// animation.
+    pub fn reference_linkage(&self) -> Option<Linkage> {
+        match &self.animation {
+            Some(animation) => animation.linkage.clone(),
+            None => self.linkage.clone(),
+        }
+    }
+
+    /// Solves the revolution in [`AppState::linkage_paths`] again if the
+    /// linkage has changed other than by the animation turning its crank.
+    pub fn update_linkage_paths(&mut self) {
+        let reference = match &self.animation {
+            Some(animation) => animation.linkage.as_ref(),
+            None => self.linkage.as_ref(),
+        };
+        if self.linkage_paths.linkage.as_ref() == reference {
+            return;
+        }
+        let linkage = reference.cloned();
+        let revolution = linkage
+            .as_ref()
+            .map(|linkage| linkage.revolution(LINKAGE_PATH_RESOLUTION))
+            .unwrap_or_default();
+        self.linkage_paths = LinkagePaths {
+            linkage,
+            revolution,
+        };
+    }
+
+    /// Finds the paths in [`AppState::four_bar_paths`] again if the
+    /// dimensions or branch of the four-bar have changed.
+    pub fn update_four_bar_paths(&mut self) {
+        // the animation turns the four-bar it started from, as every pose
+        // it solves has the same dimensions only up to rounding
+        let four_bar = match &self.animation {
+            Some(animation) => animation.four_bar,
+            None => FourBar::from_pose(&self.pose),
+        };
+        if self.four_bar_paths.four_bar != four_bar {
+            self.four_bar_paths = FourBarPaths::new(four_bar);
+        }
+    }
+
+    /// The design as it would be restored after stopping the animation.
+    pub fn design(&self) -> Design {
+        Design {
+            linkage: self.reference_linkage(),
+            tracers: self.tracers.clone(),
+            ..Design::new(self.reference_pose(), self.display)
+        }
+    }
+
+    /// The handle of the motion synthesis construction under the pointer
+    /// at `at` on the screen, if any: the ground pivots first, as they are
+    /// the least likely to be in the way, then the pins, then the
+    /// directions and coupler points of the positions.
+    pub fn position_handle(&self, at: Point) -> Option<PositionDrag> {
+        let view = self.view;
+        let near = |p: Point| length(view.to_screen(p), at) < (JOINT_RADIUS + 10.0);
+        let positions = &self.coupler_positions;
+        let first = positions.first()?;
+
+        if positions.len() == 2 {
+            for dyad in 0..2 {
+                let construction = dyad_construction(
+                    positions,
+                    self.motion_choices.moving_pivots[dyad],
+                    self.motion_choices.bisector_offsets[dyad],
+                );
+                if let Some(ground_pivot) = construction.map(|c| c.ground_pivot) {
+                    if near(ground_pivot) {
+                        return Some(PositionDrag::GroundPivot(dyad, ground_pivot));
+                    }
+                }
+            }
+        }
+        for (dyad, &pin) in self.motion_choices.moving_pivots.iter().enumerate() {
+            let pin = first.place(pin);
+            if near(pin) {
+                return Some(PositionDrag::MovingPivot(dyad, pin));
+            }
+        }
+        for (index, position) in positions.iter().enumerate() {
+            let tip = view.to_world(position_arrow_tip(view, position));
+            if near(tip) {
+                return Some(PositionDrag::Turn(index, tip));
+            }
+        }
+        positions
+            .iter()
+            .position(|position| near(position.point))
+            .map(|index| PositionDrag::Move(index, positions[index].point))
+    }
+
+    /// Angle of the (first) crank as currently shown.
+    pub fn crank_angle(&self) -> f64 {
+        match &self.linkage {
+            Some(linkage) => linkage.crank_angles().first().copied().unwrap_or(0.0),
+            None => self.pose.crank_angle(),
+        }
+    }
+
+    /// Turns the crank of the running animation to `crank_angle`.
+    ///
+    /// Returns `false`, leaving everything as it was, if there is no
+    /// animation or the mechanism cannot be assembled there.
+    pub fn turn_to(&mut self, crank_angle: f64) -> bool {
+        let Some(animation) = self.animation.as_mut() else {
+            return false;
+        };
+        let crank_angle = crank_angle.rem_euclid(TAU);
+        match (&animation.linkage, &self.linkage) {
+            (Some(reference), Some(current)) => {
+                // from the current frame, so that it stays on its branch
+                let mut angles = current.crank_angles();
+                if let Some(first) = angles.first_mut() {
+                    *first = crank_angle;
+                }
+                match reference.solve_from(current, &angles) {
+                    Ok(linkage) => self.linkage = Some(linkage),
+                    Err(_) => return false,
+                }
+            }
+            _ => match animation.four_bar.solve(crank_angle) {
+                Ok(pose) => self.pose = pose,
+                Err(_) => return false,
+            },
+        }
+        animation.crank_angle = crank_angle;
+        true
+    }
+}
+
+/// Where on the screen the arrow showing the direction of `position` ends.
+pub fn position_arrow_tip(view: View, position: &CouplerPosition) -> Point {
+    let p = view.to_screen(position.point);
+    (
+        p.0 + POSITION_ARROW_LENGTH * position.angle.cos(),
+        p.1 - POSITION_ARROW_LENGTH * position.angle.sin(),
+    )
+}
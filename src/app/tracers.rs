@@ -0,0 +1,263 @@
+//! Side panel for putting tracers on the moving links and choosing where
+//! they sit, their colour and whether they are shown.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{gdk, Button, CheckButton, ColorButton, DropDown, Grid, Label, SpinButton};
+use kinematicsolver::geometry::length;
+use kinematicsolver::{Link, Linkage, Pose, Tracer};
+
+use super::editor::{connect_value_edited, link_name, spin_button, MAX_DIMENSION};
+
+/// Colours given to new tracers in turn, leaving red to the coupler curve.
+const PALETTE: [(f64, f64, f64); 6] = [
+    (0.0, 0.4, 0.8),
+    (0.0, 0.6, 0.0),
+    (0.9, 0.5, 0.0),
+    (0.6, 0.0, 0.6),
+    (0.0, 0.6, 0.6),
+    (0.5, 0.3, 0.1),
+];
+/// How far to the side of its link a new tracer is put, in millimetres.
+const NEW_TRACER_OFFSET: f64 = 40.0;
+
+/// A change made in the tracer panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TracerEdit {
+    /// Put a new tracer on the mechanism, see [`new_tracer`].
+    Add,
+    Remove(usize),
+    /// Replace the tracer at an index.
+    Change(usize, Tracer),
+}
+
+/// The links a tracer can be put on, with their names: the moving links of
+/// the four-bar, or the moving bodies of `linkage` that have a direction.
+pub fn moving_links(linkage: Option<&Linkage>) -> Vec<(usize, String)> {
+    match linkage {
+        None => Link::ALL
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(body, &link)| (body, link_name(link).to_string()))
+            .collect(),
+        Some(linkage) => linkage
+            .bodies
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, joints)| joints.len() >= 2)
+            .map(|(body, _)| (body, format!("Body {body}")))
+            .collect(),
+    }
+}
+
+/// A tracer halfway along the coupler, or the first moving body of
+/// `linkage`, to one side of it and in the next colour after `count` other
+/// tracers.
+pub fn new_tracer(pose: &Pose, linkage: Option<&Linkage>, count: usize) -> Option<Tracer> {
+    let body = match linkage {
+        None => Link::ALL.iter().position(|&link| link == Link::Coupler)?,
+        Some(_) => moving_links(linkage).first()?.0,
+    };
+    let mut tracer = Tracer {
+        body,
+        along: 0.0,
+        across: NEW_TRACER_OFFSET,
+        color: PALETTE[count % PALETTE.len()],
+        visible: true,
+    };
+    let (start, end) = match linkage {
+        None => pose.link(tracer.link()?),
+        Some(linkage) => tracer.ends_in(linkage)?,
+    };
+    tracer.along = length(start, end) / 2.0;
+    Some(tracer)
+}
+
+/// The widgets showing one tracer.
+#[derive(Clone)]
+struct TracerRow {
+    link: DropDown,
+    along: SpinButton,
+    across: SpinButton,
+    color: ColorButton,
+    visible: CheckButton,
+}
+
+impl TracerRow {
+    /// The tracer as entered, with the link picked from `links`.
+    fn tracer(&self, links: &[(usize, String)]) -> Option<Tracer> {
+        let &(body, _) = links.get(self.link.selected() as usize)?;
+        let color = self.color.rgba();
+        Some(Tracer {
+            body,
+            along: self.along.value(),
+            across: self.across.value(),
+            color: (
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+            ),
+            visible: self.visible.is_active(),
+        })
+    }
+}
+
+/// One row for every tracer and a button to add another.
+pub struct TracerPanel {
+    container: gtk::Box,
+    grid: Grid,
+    rows: RefCell<Vec<TracerRow>>,
+    /// The links offered and the number of tracers the rows were built for.
+    built_for: RefCell<(Vec<(usize, String)>, usize)>,
+    on_edit: Rc<dyn Fn(TracerEdit)>,
+    /// Set while [`TracerPanel::update`] writes to the widgets, so that
+    /// showing the model is not mistaken for an edit.
+    updating: Rc<Cell<bool>>,
+}
+
+impl TracerPanel {
+    /// Builds the panel, calling `on_edit` whenever the user changes a
+    /// tracer.
+    pub fn new(on_edit: impl Fn(TracerEdit) + 'static) -> Self {
+        let on_edit: Rc<dyn Fn(TracerEdit)> = Rc::new(on_edit);
+        let grid = Grid::builder().row_spacing(6).column_spacing(6).build();
+        let add = Button::builder().label("Add tracer").build();
+        add.connect_clicked({
+            let on_edit = on_edit.clone();
+            move |_| on_edit(TracerEdit::Add)
+        });
+        let container = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .build();
+        container.append(&grid);
+        container.append(&add);
+
+        TracerPanel {
+            container,
+            grid,
+            rows: RefCell::default(),
+            built_for: RefCell::default(),
+            on_edit,
+            updating: Rc::default(),
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    /// Shows `tracers`, offering to put them on `links`, see
+    /// [`moving_links`].
+    ///
+    /// The rows are only rebuilt when tracers are added or removed or the
+    /// links change, so that calling this every frame does not disturb
+    /// typing.
+    pub fn update(&self, tracers: &[Tracer], links: Vec<(usize, String)>) {
+        self.updating.set(true);
+        let mut built_for = self.built_for.borrow_mut();
+        if built_for.0 != links || built_for.1 != tracers.len() {
+            self.rebuild(&links, tracers.len());
+            *built_for = (links, tracers.len());
+        }
+
+        let links = &built_for.0;
+        for (row, tracer) in self.rows.borrow().iter().zip(tracers) {
+            let selected = links
+                .iter()
+                .position(|&(body, _)| body == tracer.body)
+                .map_or(gtk::INVALID_LIST_POSITION, |index| index as u32);
+            if row.link.selected() != selected {
+                row.link.set_selected(selected);
+            }
+            for (spin, value) in [(&row.along, tracer.along), (&row.across, tracer.across)] {
+                if (spin.value() - value).abs() > 1e-9 {
+                    spin.set_value(value);
+                }
+            }
+            let (r, g, b) = tracer.color;
+            let color = gdk::RGBA::new(r as f32, g as f32, b as f32, 1.0);
+            if row.color.rgba() != color {
+                row.color.set_rgba(&color);
+            }
+            if row.visible.is_active() != tracer.visible {
+                row.visible.set_active(tracer.visible);
+            }
+        }
+        self.updating.set(false);
+    }
+
+    fn rebuild(&self, links: &[(usize, String)], count: usize) {
+        while let Some(child) = self.grid.first_child() {
+            self.grid.remove(&child);
+        }
+        let mut rows = self.rows.borrow_mut();
+        rows.clear();
+        if count == 0 {
+            return;
+        }
+
+        for (column, title) in [(0, "Link"), (1, "along [mm]"), (2, "across [mm]")] {
+            self.grid.attach(&Label::new(Some(title)), column, 0, 1, 1);
+        }
+        let names: Vec<&str> = links.iter().map(|(_, name)| name.as_str()).collect();
+        for (index, grid_row) in (0..count).zip(1..) {
+            let row = TracerRow {
+                link: DropDown::from_strings(&names),
+                along: spin_button(-MAX_DIMENSION),
+                across: spin_button(-MAX_DIMENSION),
+                color: ColorButton::new(),
+                visible: CheckButton::builder().tooltip_text("Show").build(),
+            };
+            let remove = Button::builder()
+                .icon_name("list-remove")
+                .tooltip_text("Remove the tracer")
+                .build();
+
+            // any change in the row replaces the whole tracer
+            let changed = {
+                let row = row.clone();
+                let links = links.to_vec();
+                let on_edit = self.on_edit.clone();
+                let updating = self.updating.clone();
+                move || {
+                    if updating.get() {
+                        return;
+                    }
+                    if let Some(tracer) = row.tracer(&links) {
+                        on_edit(TracerEdit::Change(index, tracer));
+                    }
+                }
+            };
+            row.link.connect_selected_notify({
+                let changed = changed.clone();
+                move |_| changed()
+            });
+            for spin in [&row.along, &row.across] {
+                let changed = changed.clone();
+                connect_value_edited(spin, move |_| changed());
+            }
+            row.color.connect_color_set({
+                let changed = changed.clone();
+                move |_| changed()
+            });
+            row.visible.connect_toggled(move |_| changed());
+            remove.connect_clicked({
+                let on_edit = self.on_edit.clone();
+                move |_| on_edit(TracerEdit::Remove(index))
+            });
+
+            self.grid.attach(&row.link, 0, grid_row, 1, 1);
+            self.grid.attach(&row.along, 1, grid_row, 1, 1);
+            self.grid.attach(&row.across, 2, grid_row, 1, 1);
+            self.grid.attach(&row.color, 3, grid_row, 1, 1);
+            self.grid.attach(&row.visible, 4, grid_row, 1, 1);
+            self.grid.attach(&remove, 5, grid_row, 1, 1);
+            rows.push(row);
+        }
+    }
+}
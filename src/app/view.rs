@@ -0,0 +1,149 @@
+//! Mapping between the mechanism's millimetres and the drawing area's pixels.
+
+use kinematicsolver::{FourBar, Joint, Linkage, Point, Pose};
+
+use super::draw::{COUPLER_CURVE_RESOLUTION, LINKAGE_PATH_RESOLUTION};
+
+/// Limits of the zoom, in pixels per millimetre.
+const MIN_SCALE: f64 = 0.01;
+const MAX_SCALE: f64 = 100.0;
+/// Space left around the mechanism by [`View::fit`], in pixels, so that the
+/// ground supports stay visible.
+const FIT_MARGIN: f64 = 60.0;
+
+/// Transform from world coordinates (millimetres, y up) to screen
+/// coordinates (pixels, y down).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    /// Pixels per millimetre.
+    pub scale: f64,
+    /// Screen position of the world origin.
+    pub origin: Point,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            scale: 1.0,
+            origin: (350.0, 550.0),
+        }
+    }
+}
+
+impl View {
+    pub fn to_screen(self, p: Point) -> Point {
+        (
+            self.origin.0 + self.scale * p.0,
+            self.origin.1 - self.scale * p.1,
+        )
+    }
+
+    pub fn to_world(self, p: Point) -> Point {
+        (
+            (p.0 - self.origin.0) / self.scale,
+            (self.origin.1 - p.1) / self.scale,
+        )
+    }
+
+    /// Maps a displacement or velocity, which unlike a position does not
+    /// depend on the origin.
+    pub fn vector_to_screen(&self, v: Point) -> Point {
+        (self.scale * v.0, -self.scale * v.1)
+    }
+
+    pub fn vector_to_world(&self, v: Point) -> Point {
+        (v.0 / self.scale, -v.1 / self.scale)
+    }
+
+    /// `pose` with every joint mapped to the screen.
+    pub fn pose_to_screen(&self, pose: &Pose) -> Pose {
+        let mut screen = *pose;
+        for joint in Joint::ALL {
+            *screen.joint_mut(joint) = self.to_screen(pose.joint(joint));
+        }
+        screen
+    }
+
+    /// Zooms by `factor`, keeping the world point under `center` in place.
+    pub fn zoom(&mut self, factor: f64, center: Point) {
+        let fixed = self.to_world(center);
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.origin = (
+            center.0 - self.scale * fixed.0,
+            center.1 + self.scale * fixed.1,
+        );
+    }
+
+    /// Moves the picture by `delta` pixels.
+    pub fn pan(&mut self, delta: Point) {
+        self.origin = (self.origin.0 + delta.0, self.origin.1 + delta.1);
+    }
+
+    /// The view showing all of `points` centred in an area of the given
+    /// size, or `None` if there are no points.
+    pub fn fit(points: impl IntoIterator<Item = Point>, width: f64, height: f64) -> Option<View> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                (min.0.min(p.0), min.1.min(p.1)),
+                (max.0.max(p.0), max.1.max(p.1)),
+            )
+        });
+
+        let available = (
+            (width - 2.0 * FIT_MARGIN).max(1.0),
+            (height - 2.0 * FIT_MARGIN).max(1.0),
+        );
+        // a single point or a straight line leaves one or both ratios
+        // infinite, which the clamp turns into the closest zoom
+        let scale = (available.0 / (max.0 - min.0))
+            .min(available.1 / (max.1 - min.1))
+            .clamp(MIN_SCALE, MAX_SCALE);
+        let center = (0.5 * (min.0 + max.0), 0.5 * (min.1 + max.1));
+
+        Some(View {
+            scale,
+            origin: (
+                0.5 * width - scale * center.0,
+                0.5 * height + scale * center.1,
+            ),
+        })
+    }
+
+    /// The view showing the joints of `pose` and its coupler curve.
+    pub fn fit_pose(pose: &Pose, width: f64, height: f64) -> View {
+        let curve = FourBar::from_pose(pose)
+            .coupler_curve(pose.crank_angle(), COUPLER_CURVE_RESOLUTION)
+            .into_iter()
+            .flatten();
+        let joints = Joint::ALL.map(|joint| pose.joint(joint));
+        // there is always at least one joint
+        View::fit(joints.into_iter().chain(curve), width, height).unwrap()
+    }
+
+    /// The view showing every joint of `linkage` over a revolution of its
+    /// first crank.
+    pub fn fit_linkage(linkage: &Linkage, width: f64, height: f64) -> View {
+        let positions: Vec<Linkage> = linkage
+            .revolution(LINKAGE_PATH_RESOLUTION)
+            .into_iter()
+            .filter_map(|(_, solved)| solved.ok())
+            .collect();
+        let points = linkage
+            .joints
+            .iter()
+            .chain(positions.iter().flat_map(|position| &position.joints))
+            .copied();
+        View::fit(points, width, height).unwrap_or_default()
+    }
+
+    /// The view showing the mechanism, `linkage` if there is one, otherwise
+    /// the four-bar in `pose`.
+    pub fn fit_mechanism(pose: &Pose, linkage: Option<&Linkage>, width: f64, height: f64) -> View {
+        match linkage {
+            Some(linkage) => View::fit_linkage(linkage, width, height),
+            None => View::fit_pose(pose, width, height),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Saving and loading mechanisms as human-readable JSON files.
+//!
+//! A design file looks like this, with positions in millimetres and the
+//! y-axis pointing up:
+//!
+//! ```json
+//! {
+//!   "version": 4,
+//!   "pose": {
+//!     "crank_pivot": [0.0, 0.0],
+//!     "crank_pin": [-50.0, 150.0],
+//!     "rocker_pin": [200.0, 200.0],
+//!     "rocker_pivot": [250.0, -50.0],
+//!     "coupler_point": [90.0, 0.0]
+//!   },
+//!   "branch": "open",
+//!   "display": {
+//!     "show_both_branches": false,
+//!     "min_transmission_angle": 40.0,
+//!     "show_velocities": false,
+//!     "show_accelerations": false
+//!   }
+//! }
+//! ```
+//!
+//! Mechanisms other than a four-bar are stored as a general linkage under
+//! `"linkage"`, see [`Linkage`]; `pose` and `branch` then keep the four-bar
+//! the design started from.
+//!
+//! Points traced besides the coupler point are listed under `"tracers"`,
+//! see [`Tracer`].
+//!
+//! Version 1 files stored screen pixels with the y-axis pointing down. They
+//! are read as millimetres and mirrored, so they look the same as before.
+//! Version 2 files cannot hold a general linkage and version 3 files no
+//! tracers, but otherwise they read the same.
+
+use std::path::Path;
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::four_bar::{Branch, FourBar, Joint, Pose};
+use crate::linkage::{Linkage, GROUND};
+use crate::tracer::Tracer;
+
+/// The newest file format version this crate reads and the one it writes.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Options that change how a mechanism is drawn, but not the mechanism.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Also trace the coupler curve of the branch the linkage is not on.
+    pub show_both_branches: bool,
+    /// Transmission angles closer than this to 0° or 180° are flagged, in
+    /// degrees.
+    pub min_transmission_angle: f64,
+    /// Draw velocity vectors at the moving joints.
+    pub show_velocities: bool,
+    /// Draw acceleration vectors at the moving joints.
+    pub show_accelerations: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            show_both_branches: false,
+            min_transmission_angle: 40.0,
+            show_velocities: false,
+            show_accelerations: false,
+        }
+    }
+}
+
+/// A mechanism together with how it is displayed, as stored on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Design {
+    pub version: u32,
+    /// Positions of all joints and the coupler point.
+    pub pose: Pose,
+    pub branch: Branch,
+    /// The mechanism, when it is not a four-bar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linkage: Option<Linkage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracers: Vec<Tracer>,
+    #[serde(default)]
+    pub display: DisplaySettings,
+}
+
+/// Errors while reading or writing a design file.
+#[derive(Debug)]
+pub enum DesignError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The file was written by a newer version of this program.
+    UnsupportedVersion(u32),
+    /// The tracer at this index into [`Design::tracers`] is on the ground,
+    /// which does not move.
+    GroundTracer(usize),
+}
+
+impl fmt::Display for DesignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DesignError::Io(err) => write!(f, "{err}"),
+            DesignError::Json(err) => write!(f, "invalid design file: {err}"),
+            DesignError::UnsupportedVersion(version) => write!(
+                f,
+                "design file has version {version}, but only up to {FORMAT_VERSION} is supported"
+            ),
+            DesignError::GroundTracer(index) => write!(
+                f,
+                "tracer {} is on the ground, which does not move",
+                index + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DesignError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DesignError::Io(err) => Some(err),
+            DesignError::Json(err) => Some(err),
+            DesignError::UnsupportedVersion(_) | DesignError::GroundTracer(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for DesignError {
+    fn from(err: io::Error) -> Self {
+        DesignError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for DesignError {
+    fn from(err: serde_json::Error) -> Self {
+        DesignError::Json(err)
+    }
+}
+
+impl Design {
+    /// Describes `pose` in the current format version.
+    pub fn new(pose: Pose, display: DisplaySettings) -> Self {
+        Design {
+            version: FORMAT_VERSION,
+            pose,
+            branch: FourBar::from_pose(&pose).branch,
+            linkage: None,
+            tracers: Vec::new(),
+            display,
+        }
+    }
+
+    /// The stored pose, reassembled on the stored branch if the joint
+    /// positions disagree with it.
+    pub fn pose(&self) -> Pose {
+        let four_bar = FourBar {
+            branch: self.branch,
+            ..FourBar::from_pose(&self.pose)
+        };
+        four_bar.solve(self.pose.crank_angle()).unwrap_or(self.pose)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DesignError> {
+        // check the version first, newer files may not parse as this one
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        if let Some(version) = value.get("version").and_then(|v| v.as_u64()) {
+            if version > FORMAT_VERSION as u64 {
+                return Err(DesignError::UnsupportedVersion(version as u32));
+            }
+        }
+        let mut design: Design = serde_json::from_value(value)?;
+        if let Some(index) = design.tracers.iter().position(|t| t.body == GROUND) {
+            return Err(DesignError::GroundTracer(index));
+        }
+        if design.version < 2 {
+            for joint in Joint::ALL {
+                let p = design.pose.joint_mut(joint);
+                p.1 = -p.1;
+            }
+            design.version = FORMAT_VERSION;
+        }
+        Ok(design)
+    }
+
+    pub fn to_json(&self) -> String {
+        // plain data with string keys always serialises
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DesignError> {
+        Design::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DesignError> {
+        fs::write(path, self.to_json() + "\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates;
+
+    #[test]
+    fn version_1_is_mirrored_and_keeps_its_branch() {
+        let json = r#"{
+            "version": 1,
+            "pose": {
+                "crank_pivot": [0.0, 0.0],
+                "crank_pin": [-50.0, -150.0],
+                "rocker_pin": [200.0, -200.0],
+                "rocker_pivot": [250.0, 50.0],
+                "coupler_point": [90.0, 0.0]
+            },
+            "branch": "crossed"
+        }"#;
+        let design = Design::from_json(json).unwrap();
+        assert_eq!(design.version, FORMAT_VERSION);
+        assert_eq!(design.pose, templates::four_bar());
+        assert_eq!(design.branch, Branch::Crossed);
+        assert_eq!(design.display, DisplaySettings::default());
+        assert_eq!(design.linkage, None);
+        assert!(design.tracers.is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let display = DisplaySettings {
+            show_velocities: true,
+            ..DisplaySettings::default()
+        };
+        let design = Design::new(templates::four_bar(), display);
+        assert_eq!(Design::from_json(&design.to_json()).unwrap(), design);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let json = format!(r#"{{"version": {}}}"#, FORMAT_VERSION + 1);
+        assert!(matches!(
+            Design::from_json(&json),
+            Err(DesignError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn tracers_on_the_ground_are_refused() {
+        let mut design = Design::new(templates::four_bar(), DisplaySettings::default());
+        let tracer = Tracer {
+            body: 2,
+            along: 100.0,
+            across: 20.0,
+            color: (1.0, 0.0, 0.0),
+            visible: true,
+        };
+        design.tracers = vec![
+            tracer,
+            Tracer {
+                body: GROUND,
+                ..tracer
+            },
+        ];
+        assert!(matches!(
+            Design::from_json(&design.to_json()),
+            Err(DesignError::GroundTracer(1))
+        ));
+        design.tracers.pop();
+        assert_eq!(Design::from_json(&design.to_json()).unwrap(), design);
+    }
+}
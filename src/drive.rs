@@ -0,0 +1,179 @@
+//! Moving the linkage by one of its moving joints instead of the crank.
+//!
+//! The crank angle that brings a joint closest to a target is found
+//! numerically: the joint's path is sampled over a revolution, every local
+//! minimum of the distance is refined, and among the (nearly) closest ones
+//! the one nearest the current crank angle wins. The rocker pin, for
+//! example, passes every point of its arc twice per revolution, and
+//! preferring the nearby solution keeps a drag from jumping between them.
+
+use std::f64::consts::PI;
+
+use crate::four_bar::{FourBar, Joint, Link, Pose};
+use crate::geometry::{angle, length, Point};
+
+/// Number of crank angles sampled over a revolution before refining.
+const SAMPLES: usize = 720;
+/// Golden-section iterations when refining a sampled minimum.
+const REFINE_ITERATIONS: usize = 40;
+/// Minima this much (relative to the size of the linkage) further from the
+/// target than the closest one still count as equally close.
+const TIE_TOLERANCE: f64 = 1e-3;
+
+/// Absolute difference of two angles, between 0 and π.
+fn angle_between(a: f64, b: f64) -> f64 {
+    ((a - b + PI).rem_euclid(2.0 * PI) - PI).abs()
+}
+
+impl FourBar {
+    /// Solves the pose with `joint` as close as possible to `target`, with
+    /// all link lengths kept, starting from `crank_angle`.
+    ///
+    /// Returns `None` for the ground pivots, which do not move, and if the
+    /// linkage cannot be assembled at any crank angle.
+    pub fn drive(&self, joint: Joint, target: Point, crank_angle: f64) -> Option<Pose> {
+        if joint.is_ground() {
+            return None;
+        }
+        if joint == Joint::CrankPin {
+            // the crank pin can follow exactly wherever the linkage assembles
+            if let Ok(pose) = self.solve(angle(target, self.crank_pivot)) {
+                return Some(pose);
+            }
+        }
+
+        let distance = |a: f64| match self.solve(a) {
+            Ok(pose) => length(pose.joint(joint), target),
+            Err(_) => f64::INFINITY,
+        };
+        let step = 2.0 * PI / SAMPLES as f64;
+        let samples: Vec<f64> = (0..SAMPLES).map(|i| distance(i as f64 * step)).collect();
+
+        let mut minima = Vec::new();
+        for (i, &d) in samples.iter().enumerate() {
+            let before = samples[(i + SAMPLES - 1) % SAMPLES];
+            let after = samples[(i + 1) % SAMPLES];
+            if d.is_finite() && d <= before && d <= after {
+                let sample = i as f64 * step;
+                let refined = refine(&distance, sample - step, sample + step);
+                let refined_distance = distance(refined);
+                minima.push(if refined_distance < d {
+                    (refined, refined_distance)
+                } else {
+                    (sample, d)
+                });
+            }
+        }
+
+        let closest = minima.iter().map(|&(_, d)| d).fold(f64::INFINITY, f64::min);
+        let size: f64 = Link::ALL.iter().map(|&link| self.length(link)).sum();
+        let (a, _) = minima
+            .into_iter()
+            .filter(|&(_, d)| d <= closest + TIE_TOLERANCE * size)
+            .min_by(|x, y| {
+                angle_between(x.0, crank_angle).total_cmp(&angle_between(y.0, crank_angle))
+            })?;
+        self.solve(a).ok()
+    }
+}
+
+/// Golden-section search for the minimum of `f` between `lo` and `hi`.
+fn refine(f: &impl Fn(f64) -> f64, mut lo: f64, mut hi: f64) -> f64 {
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut x1 = hi - ratio * (hi - lo);
+    let mut x2 = lo + ratio * (hi - lo);
+    let (mut f1, mut f2) = (f(x1), f(x2));
+    for _ in 0..REFINE_ITERATIONS {
+        if f1 <= f2 {
+            hi = x2;
+            (x2, f2) = (x1, f1);
+            x1 = hi - ratio * (hi - lo);
+            f1 = f(x1);
+        } else {
+            lo = x1;
+            (x1, f1) = (x2, f2);
+            x2 = lo + ratio * (hi - lo);
+            f2 = f(x2);
+        }
+    }
+    if f1 <= f2 {
+        x1
+    } else {
+        x2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four_bar::{Branch, CouplerPoint};
+    use crate::templates;
+
+    /// How close a refined minimum comes, in millimetres: a distance is
+    /// flat at its minimum, so it is only found to about the square root
+    /// of the rounding error.
+    const TOLERANCE: f64 = 1e-4;
+
+    #[test]
+    fn crank_pin_follows_exactly() {
+        let four_bar = FourBar::from_pose(&templates::four_bar());
+        let target = (100.0, 80.0);
+        let pose = four_bar.drive(Joint::CrankPin, target, 0.0).unwrap();
+        assert!((pose.crank_angle() - angle(target, four_bar.crank_pivot)).abs() < 1e-12);
+        // the pin stays on its circle, pointing at the target
+        let pin = pose.crank_pin;
+        assert!((length(pin, four_bar.crank_pivot) - four_bar.crank_length).abs() < 1e-9);
+        let cross = pin.0 * target.1 - pin.1 * target.0;
+        assert!(cross.abs() < 1e-9 && pin.0 * target.0 + pin.1 * target.1 > 0.0);
+    }
+
+    #[test]
+    fn rocker_pin_goes_to_the_nearest_point_of_its_path() {
+        let start = templates::four_bar();
+        let four_bar = FourBar::from_pose(&start);
+        // straight out from the rocker pivot, past the rocker pin
+        let (pivot, pin) = (start.rocker_pivot, start.rocker_pin);
+        let target = (2.0 * pin.0 - pivot.0, 2.0 * pin.1 - pivot.1);
+
+        let pose = four_bar
+            .drive(Joint::RockerPin, target, start.crank_angle())
+            .unwrap();
+        assert!(length(pose.rocker_pin, pin) < TOLERANCE);
+        assert!(
+            angle_between(pose.crank_angle(), start.crank_angle())
+                < TOLERANCE / four_bar.crank_length
+        );
+
+        // the rocker pin passes there twice a revolution; starting across
+        // the circle picks the other crank angle
+        let other = four_bar
+            .drive(Joint::RockerPin, target, start.crank_angle() + PI)
+            .unwrap();
+        assert!(length(other.rocker_pin, pin) < TOLERANCE);
+        assert!(angle_between(other.crank_angle(), start.crank_angle()) > 0.1);
+    }
+
+    #[test]
+    fn ground_pivots_do_not_move() {
+        let four_bar = FourBar::from_pose(&templates::four_bar());
+        for joint in [Joint::CrankPivot, Joint::RockerPivot] {
+            assert_eq!(four_bar.drive(joint, (10.0, 10.0), 0.0), None);
+        }
+    }
+
+    #[test]
+    fn linkages_that_never_assemble_do_not_move() {
+        let four_bar = FourBar {
+            crank_pivot: (0.0, 0.0),
+            rocker_pivot: (1000.0, 0.0),
+            crank_length: 100.0,
+            coupler_length: 200.0,
+            rocker_length: 200.0,
+            coupler_point: CouplerPoint::default(),
+            branch: Branch::Open,
+        };
+        for joint in [Joint::CrankPin, Joint::RockerPin, Joint::CouplerPoint] {
+            assert_eq!(four_bar.drive(joint, (100.0, 0.0), 0.0), None);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Export of sampled mechanism data for use in other tools.
+
+use std::io::{self, Write};
+
+use crate::four_bar::FourBar;
+use crate::linkage::Linkage;
+use crate::tracer::Tracer;
+
+/// Column names of the CSV written by [`write_csv`], which adds
+/// `tracer1_x,tracer1_y` and so on for every tracer.
+pub const CSV_HEADER: &str =
+    "crank_angle,coupler_x,coupler_y,crank_pin_x,crank_pin_y,rocker_pin_x,rocker_pin_y";
+
+/// Writes the linkage's motion over one crank revolution as CSV, one row for
+/// each of `steps` crank angles from 0° (inclusive) to 360° (exclusive).
+///
+/// The crank angle is in degrees. Where the linkage cannot be assembled, or
+/// a tracer is not on one of its links, the position columns are left
+/// empty. Hidden tracers are written too.
+pub fn write_csv(
+    four_bar: &FourBar,
+    tracers: &[Tracer],
+    steps: usize,
+    mut out: impl Write,
+) -> io::Result<()> {
+    write!(out, "{CSV_HEADER}")?;
+    write_tracer_header(&mut out, tracers)?;
+    for (angle, pose) in four_bar.revolution(0.0, steps).take(steps) {
+        write!(out, "{}", angle.to_degrees())?;
+        let Ok(pose) = pose else {
+            writeln!(out, "{}", ",,".repeat(3 + tracers.len()))?;
+            continue;
+        };
+        for p in [pose.coupler_point, pose.crank_pin, pose.rocker_pin] {
+            write!(out, ",{},{}", p.0, p.1)?;
+        }
+        for tracer in tracers {
+            match tracer.on_pose(&pose) {
+                Some(p) => write!(out, ",{},{}", p.0, p.1)?,
+                None => write!(out, ",,")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+/// Writes the general linkage's motion over one revolution of its first
+/// crank as CSV, one row for each of `steps` crank angles from the stored
+/// one (inclusive) to a full turn on (exclusive), see
+/// [`Linkage::revolution`].
+///
+/// The columns are the crank angle in degrees, `joint0_x,joint0_y` and so
+/// on for every joint, numbered as in [`Linkage::joints`], and
+/// `tracer1_x,tracer1_y` and so on for every tracer. Where the linkage
+/// cannot be assembled, or a tracer is not on one of its links, the
+/// position columns are left empty.
+pub fn write_linkage_csv(
+    linkage: &Linkage,
+    tracers: &[Tracer],
+    steps: usize,
+    mut out: impl Write,
+) -> io::Result<()> {
+    write!(out, "crank_angle")?;
+    for i in 0..linkage.joints.len() {
+        write!(out, ",joint{i}_x,joint{i}_y")?;
+    }
+    write_tracer_header(&mut out, tracers)?;
+    for (angle, solved) in linkage.revolution(steps).into_iter().take(steps) {
+        write!(out, "{}", angle.to_degrees())?;
+        let Ok(solved) = solved else {
+            writeln!(out, "{}", ",,".repeat(linkage.joints.len() + tracers.len()))?;
+            continue;
+        };
+        for p in &solved.joints {
+            write!(out, ",{},{}", p.0, p.1)?;
+        }
+        for tracer in tracers {
+            match tracer.on_linkage(&solved) {
+                Some(p) => write!(out, ",{},{}", p.0, p.1)?,
+                None => write!(out, ",,")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+/// Ends the header with two columns for every tracer.
+fn write_tracer_header(out: &mut impl Write, tracers: &[Tracer]) -> io::Result<()> {
+    for i in 1..=tracers.len() {
+        write!(out, ",tracer{i}_x,tracer{i}_y")?;
+    }
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four_bar::Branch;
+    use crate::templates;
+
+    fn csv(four_bar: &FourBar, tracers: &[Tracer], steps: usize) -> Vec<Vec<String>> {
+        let mut out = Vec::new();
+        write_csv(four_bar, tracers, steps, &mut out).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| line.split(',').map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn header_and_rows() {
+        let four_bar = FourBar::from_pose(&templates::four_bar());
+        let tracer = Tracer {
+            body: 2,
+            along: 100.0,
+            across: 20.0,
+            color: (0.0, 0.0, 1.0),
+            visible: false,
+        };
+        let rows = csv(&four_bar, &[tracer], 4);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(
+            rows[0].join(","),
+            format!("{CSV_HEADER},tracer1_x,tracer1_y")
+        );
+        for (i, row) in rows[1..].iter().enumerate() {
+            let values: Vec<f64> = row.iter().map(|v| v.parse().unwrap()).collect();
+            assert_eq!(values[0], 90.0 * i as f64);
+            let pose = four_bar.solve(values[0].to_radians()).unwrap();
+            let tracer_point = tracer.on_pose(&pose).unwrap();
+            for (k, p) in [
+                pose.coupler_point,
+                pose.crank_pin,
+                pose.rocker_pin,
+                tracer_point,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                assert!((values[1 + 2 * k] - p.0).abs() < 1e-9);
+                assert!((values[2 + 2 * k] - p.1).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn linkage_header_and_rows() {
+        let linkage = templates::watt_i(0.0);
+        let tracer = Tracer {
+            body: 2,
+            along: 50.0,
+            across: -10.0,
+            color: (0.0, 0.0, 1.0),
+            visible: true,
+        };
+        let mut out = Vec::new();
+        write_linkage_csv(&linkage, &[tracer], 6, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let rows: Vec<Vec<&str>> = out.lines().map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 7);
+        let joints = linkage.joints.len();
+        assert_eq!(rows[0].len(), 1 + 2 * (joints + 1));
+        assert_eq!(rows[0][..3], ["crank_angle", "joint0_x", "joint0_y"]);
+        assert_eq!(rows[0][2 * joints + 1..], ["tracer1_x", "tracer1_y"]);
+        for (row, (angle, solved)) in rows[1..].iter().zip(linkage.revolution(6)) {
+            let values: Vec<f64> = row.iter().map(|v| v.parse().unwrap()).collect();
+            assert_eq!(values[0], angle.to_degrees());
+            let solved = solved.unwrap();
+            let tracer_point = tracer.on_linkage(&solved).unwrap();
+            for (k, p) in solved.joints.iter().chain([&tracer_point]).enumerate() {
+                assert_eq!(values[1 + 2 * k], p.0);
+                assert_eq!(values[2 + 2 * k], p.1);
+            }
+        }
+    }
+
+    #[test]
+    fn rows_that_cannot_be_assembled_are_empty() {
+        let four_bar = FourBar {
+            crank_pivot: (0.0, 0.0),
+            rocker_pivot: (1000.0, 0.0),
+            crank_length: 100.0,
+            coupler_length: 200.0,
+            rocker_length: 200.0,
+            coupler_point: Default::default(),
+            branch: Branch::Open,
+        };
+        let rows = csv(&four_bar, &[], 2);
+        assert_eq!(rows.len(), 3);
+        for row in &rows[1..] {
+            assert_eq!(row.len(), 7);
+            assert!(row[1..].iter().all(String::is_empty));
+        }
+    }
+}
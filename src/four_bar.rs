@@ -0,0 +1,488 @@
+//! Position analysis of the four-bar linkage.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{angle, length, Point};
+
+/// The four links of a four-bar linkage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Link {
+    /// The fixed frame between the two ground pivots.
+    Ground,
+    /// The driven link, rotating about the crank pivot.
+    Crank,
+    /// The floating link between crank and rocker.
+    Coupler,
+    /// The output link, rotating about the rocker pivot.
+    Rocker,
+}
+
+impl Link {
+    pub const ALL: [Link; 4] = [Link::Ground, Link::Crank, Link::Coupler, Link::Rocker];
+
+    /// The joints at either end of the link.
+    pub fn joints(self) -> (Joint, Joint) {
+        match self {
+            Link::Ground => (Joint::CrankPivot, Joint::RockerPivot),
+            Link::Crank => (Joint::CrankPivot, Joint::CrankPin),
+            Link::Coupler => (Joint::CrankPin, Joint::RockerPin),
+            Link::Rocker => (Joint::RockerPivot, Joint::RockerPin),
+        }
+    }
+}
+
+/// The joints of a four-bar linkage, including the traced coupler point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Joint {
+    /// Ground pivot of the crank.
+    CrankPivot,
+    /// Joint between crank and coupler.
+    CrankPin,
+    /// Joint between coupler and rocker.
+    RockerPin,
+    /// Ground pivot of the rocker.
+    RockerPivot,
+    /// Traced point rigidly attached to the coupler.
+    CouplerPoint,
+}
+
+impl Joint {
+    pub const ALL: [Joint; 5] = [
+        Joint::CrankPivot,
+        Joint::CrankPin,
+        Joint::RockerPin,
+        Joint::RockerPivot,
+        Joint::CouplerPoint,
+    ];
+
+    /// Whether the joint is fixed to the ground.
+    pub fn is_ground(self) -> bool {
+        matches!(self, Joint::CrankPivot | Joint::RockerPivot)
+    }
+}
+
+/// Positions of all joints of a four-bar linkage at one crank angle.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub crank_pivot: Point,
+    pub crank_pin: Point,
+    pub rocker_pin: Point,
+    pub rocker_pivot: Point,
+    pub coupler_point: Point,
+}
+
+impl Pose {
+    pub fn joint(&self, joint: Joint) -> Point {
+        match joint {
+            Joint::CrankPivot => self.crank_pivot,
+            Joint::CrankPin => self.crank_pin,
+            Joint::RockerPin => self.rocker_pin,
+            Joint::RockerPivot => self.rocker_pivot,
+            Joint::CouplerPoint => self.coupler_point,
+        }
+    }
+
+    pub fn joint_mut(&mut self, joint: Joint) -> &mut Point {
+        match joint {
+            Joint::CrankPivot => &mut self.crank_pivot,
+            Joint::CrankPin => &mut self.crank_pin,
+            Joint::RockerPin => &mut self.rocker_pin,
+            Joint::RockerPivot => &mut self.rocker_pivot,
+            Joint::CouplerPoint => &mut self.coupler_point,
+        }
+    }
+
+    /// Positions of the joints at either end of `link`.
+    pub fn link(&self, link: Link) -> (Point, Point) {
+        let (a, b) = link.joints();
+        (self.joint(a), self.joint(b))
+    }
+
+    /// Length of `link` as drawn in this pose.
+    pub fn length(&self, link: Link) -> f64 {
+        let (a, b) = self.link(link);
+        length(a, b)
+    }
+
+    /// Angle of the crank, measured counter-clockwise from the x-axis.
+    pub fn crank_angle(&self) -> f64 {
+        angle(self.crank_pin, self.crank_pivot)
+    }
+
+    /// Angle of the coupler from the crank pin towards the rocker pin.
+    pub fn coupler_angle(&self) -> f64 {
+        angle(self.rocker_pin, self.crank_pin)
+    }
+
+    /// Angle of the rocker from its pivot towards the rocker pin.
+    pub fn rocker_angle(&self) -> f64 {
+        angle(self.rocker_pin, self.rocker_pivot)
+    }
+
+    /// This pose with `link` resized to `length`, keeping the crank angle,
+    /// the branch and all other dimensions.
+    ///
+    /// The crank pivot stays in place; changing the ground length moves the
+    /// rocker pivot along the ground line.
+    pub fn with_length(&self, link: Link, length: f64) -> Result<Pose, AssemblyError> {
+        let mut four_bar = FourBar::from_pose(self);
+        match link {
+            Link::Ground => {
+                let ground = self.length(Link::Ground);
+                let direction = if ground > 0.0 {
+                    (
+                        (self.rocker_pivot.0 - self.crank_pivot.0) / ground,
+                        (self.rocker_pivot.1 - self.crank_pivot.1) / ground,
+                    )
+                } else {
+                    (1.0, 0.0)
+                };
+                four_bar.rocker_pivot = (
+                    self.crank_pivot.0 + length * direction.0,
+                    self.crank_pivot.1 + length * direction.1,
+                );
+            }
+            Link::Crank => four_bar.crank_length = length,
+            Link::Coupler => four_bar.coupler_length = length,
+            Link::Rocker => four_bar.rocker_length = length,
+        }
+        four_bar.solve(self.crank_angle())
+    }
+}
+
+/// The two ways a four-bar with given dimensions can be assembled.
+///
+/// At every crank angle the rocker pin is one of the two intersections of
+/// the coupler and rocker circles. Each branch keeps the rocker pin on the
+/// same side of the line from the crank pin to the rocker pivot, which gives
+/// one continuous circuit per branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Branch {
+    /// The rocker pin lies counter-clockwise of the line from
+    /// the crank pin to the rocker pivot.
+    #[default]
+    Open,
+    /// The mirror image of the open branch about that line.
+    Crossed,
+}
+
+impl Branch {
+    pub const ALL: [Branch; 2] = [Branch::Open, Branch::Crossed];
+
+    /// The other branch.
+    pub fn opposite(self) -> Branch {
+        match self {
+            Branch::Open => Branch::Crossed,
+            Branch::Crossed => Branch::Open,
+        }
+    }
+
+    /// Sign of the square root in the circle-circle intersection.
+    fn sign(self) -> f64 {
+        match self {
+            Branch::Open => 1.0,
+            Branch::Crossed => -1.0,
+        }
+    }
+}
+
+/// Position of the coupler point in the coupler's own frame.
+///
+/// The origin is the crank pin and the x-axis points towards the rocker pin.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CouplerPoint {
+    /// Distance along the coupler, measured from the crank pin.
+    pub along: f64,
+    /// Distance perpendicular to the coupler, positive to its left.
+    pub across: f64,
+}
+
+/// Returned when the coupler and rocker cannot be joined at a crank angle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssemblyError {
+    /// The rocker pivot is out of reach of coupler and rocker stretched out.
+    TooFar {
+        /// Distance from the crank pin to the rocker pivot.
+        distance: f64,
+        /// Sum of coupler and rocker length.
+        reach: f64,
+    },
+    /// The rocker pivot is closer to the crank pin than coupler and rocker
+    /// folded onto each other.
+    TooClose {
+        /// Distance from the crank pin to the rocker pivot.
+        distance: f64,
+        /// Difference of coupler and rocker length.
+        reach: f64,
+    },
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyError::TooFar { distance, reach } => write!(
+                f,
+                "cannot assemble: rocker pivot is {distance:.1} mm away, but coupler and rocker only reach {reach:.1} mm"
+            ),
+            AssemblyError::TooClose { distance, reach } => write!(
+                f,
+                "cannot assemble: rocker pivot is {distance:.1} mm away, but coupler and rocker cannot fold closer than {reach:.1} mm"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+/// Dimensions of a four-bar linkage, independent of its current position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FourBar {
+    pub crank_pivot: Point,
+    pub rocker_pivot: Point,
+    pub crank_length: f64,
+    pub coupler_length: f64,
+    pub rocker_length: f64,
+    pub coupler_point: CouplerPoint,
+    pub branch: Branch,
+}
+
+impl FourBar {
+    /// Extracts the dimensions of the linkage shown in `pose`.
+    pub fn from_pose(pose: &Pose) -> Self {
+        let coupler_length = pose.length(Link::Coupler);
+        let u = (
+            (pose.rocker_pin.0 - pose.crank_pin.0) / coupler_length,
+            (pose.rocker_pin.1 - pose.crank_pin.1) / coupler_length,
+        );
+        let d = (
+            pose.coupler_point.0 - pose.crank_pin.0,
+            pose.coupler_point.1 - pose.crank_pin.1,
+        );
+        let g = (
+            pose.rocker_pivot.0 - pose.crank_pin.0,
+            pose.rocker_pivot.1 - pose.crank_pin.1,
+        );
+        // positive when the rocker pin is counter-clockwise of the line
+        let side = g.0 * u.1 - g.1 * u.0;
+
+        FourBar {
+            crank_pivot: pose.crank_pivot,
+            rocker_pivot: pose.rocker_pivot,
+            crank_length: pose.length(Link::Crank),
+            coupler_length,
+            rocker_length: pose.length(Link::Rocker),
+            coupler_point: CouplerPoint {
+                along: d.0 * u.0 + d.1 * u.1,
+                across: d.1 * u.0 - d.0 * u.1,
+            },
+            branch: if side >= 0.0 {
+                Branch::Open
+            } else {
+                Branch::Crossed
+            },
+        }
+    }
+
+    /// Length of `link`.
+    pub fn length(&self, link: Link) -> f64 {
+        match link {
+            Link::Ground => length(self.crank_pivot, self.rocker_pivot),
+            Link::Crank => self.crank_length,
+            Link::Coupler => self.coupler_length,
+            Link::Rocker => self.rocker_length,
+        }
+    }
+
+    /// Solves the position of every joint for the given crank angle.
+    pub fn solve(&self, crank_angle: f64) -> Result<Pose, AssemblyError> {
+        let crank_pin = (
+            self.crank_pivot.0 + self.crank_length * crank_angle.cos(),
+            self.crank_pivot.1 + self.crank_length * crank_angle.sin(),
+        );
+        let rocker_pin = self.rocker_pos(crank_pin)?;
+
+        let u = (
+            (rocker_pin.0 - crank_pin.0) / self.coupler_length,
+            (rocker_pin.1 - crank_pin.1) / self.coupler_length,
+        );
+        let CouplerPoint { along, across } = self.coupler_point;
+        let coupler_point = (
+            crank_pin.0 + along * u.0 - across * u.1,
+            crank_pin.1 + along * u.1 + across * u.0,
+        );
+
+        Ok(Pose {
+            crank_pivot: self.crank_pivot,
+            crank_pin,
+            rocker_pin,
+            rocker_pivot: self.rocker_pivot,
+            coupler_point,
+        })
+    }
+
+    /// Solves the linkage at `steps + 1` evenly spaced crank angles covering
+    /// one revolution from `crank_angle`, both ends included.
+    pub fn revolution(
+        &self,
+        crank_angle: f64,
+        steps: usize,
+    ) -> impl Iterator<Item = (f64, Result<Pose, AssemblyError>)> + '_ {
+        (0..=steps).map(move |i| {
+            let a = std::f64::consts::PI * 2.0 * i as f64 / steps as f64 + crank_angle;
+            (a, self.solve(a))
+        })
+    }
+
+    /// Samples the path of the coupler point over one crank revolution,
+    /// starting and ending at `crank_angle`.
+    ///
+    /// The path is split into separate segments wherever the linkage cannot
+    /// be assembled, so every returned point is valid.
+    pub fn coupler_curve(&self, crank_angle: f64, steps: usize) -> Vec<Vec<Point>> {
+        self.trace(crank_angle, steps, |pose| pose.coupler_point)
+    }
+
+    /// Samples the path of any point moving with the linkage, found by
+    /// `point` in each pose, in the same way as [`FourBar::coupler_curve`].
+    pub fn trace(
+        &self,
+        crank_angle: f64,
+        steps: usize,
+        point: impl Fn(&Pose) -> Point,
+    ) -> Vec<Vec<Point>> {
+        let mut segments: Vec<Vec<Point>> = Vec::new();
+        let mut current = Vec::new();
+
+        for (_, pose) in self.revolution(crank_angle, steps) {
+            match pose {
+                Ok(pose) => current.push(point(&pose)),
+                Err(_) => {
+                    if !current.is_empty() {
+                        segments.push(std::mem::take(&mut current));
+                    }
+                }
+            }
+        }
+        if !current.is_empty() {
+            // the last segment ends where the first one starts
+            match segments.first_mut() {
+                Some(first) if self.solve(crank_angle).is_ok() => {
+                    current.pop();
+                    current.append(first);
+                    *first = current;
+                }
+                _ => segments.push(current),
+            }
+        }
+
+        segments
+    }
+
+    /// Intersects the circles of the coupler around `p1` and of the rocker
+    /// around its ground pivot.
+    fn rocker_pos(&self, p1: Point) -> Result<Point, AssemblyError> {
+        let p2 = self.rocker_pivot;
+        let r = length(p1, p2);
+        let c2 = self.coupler_length.powf(2.0);
+        let r2 = self.rocker_length.powf(2.0);
+
+        let discriminant = 2.0 * (c2 + r2) / r.powf(2.0) - (c2 - r2).powf(2.0) / r.powf(4.0) - 1.0;
+        // NaN when the crank pin sits on the rocker pivot
+        if discriminant.is_nan() || discriminant < 0.0 {
+            let reach = self.coupler_length + self.rocker_length;
+            return Err(if r > reach {
+                AssemblyError::TooFar { distance: r, reach }
+            } else {
+                AssemblyError::TooClose {
+                    distance: r,
+                    reach: (self.coupler_length - self.rocker_length).abs(),
+                }
+            });
+        }
+
+        let k = (c2 - r2) / (2.0 * r.powf(2.0));
+        let h = self.branch.sign() * 0.5 * discriminant.sqrt();
+        Ok((
+            0.5 * (p1.0 + p2.0) + k * (p2.0 - p1.0) + h * (p1.1 - p2.1),
+            0.5 * (p1.1 + p2.1) + k * (p2.1 - p1.1) + h * (p2.0 - p1.0),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates;
+
+    fn assert_close(a: Point, b: Point) {
+        assert!(length(a, b) < 1e-9, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn solve_reproduces_the_pose_it_was_taken_from() {
+        let pose = templates::four_bar();
+        let four_bar = FourBar::from_pose(&pose);
+        assert_eq!(four_bar.branch, Branch::Open);
+        let solved = four_bar.solve(pose.crank_angle()).unwrap();
+        for joint in Joint::ALL {
+            assert_close(solved.joint(joint), pose.joint(joint));
+        }
+    }
+
+    #[test]
+    fn crossed_branch_mirrors_the_rocker_pin() {
+        let pose = templates::four_bar();
+        let crossed = FourBar {
+            branch: Branch::Crossed,
+            ..FourBar::from_pose(&pose)
+        };
+        let solved = crossed.solve(pose.crank_angle()).unwrap();
+        assert_close(solved.crank_pin, pose.crank_pin);
+        assert_ne!(solved.rocker_pin, pose.rocker_pin);
+        for link in Link::ALL {
+            assert!((solved.length(link) - pose.length(link)).abs() < 1e-9);
+        }
+        // mirrored about the line from the crank pin to the rocker pivot
+        let (a, b) = (pose.crank_pin, pose.rocker_pivot);
+        let side = |p: Point| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+        assert!(side(pose.rocker_pin) * side(solved.rocker_pin) < 0.0);
+        assert_eq!(FourBar::from_pose(&solved).branch, Branch::Crossed);
+    }
+
+    #[test]
+    fn assembly_errors() {
+        let four_bar = FourBar {
+            crank_pivot: (0.0, 0.0),
+            rocker_pivot: (1000.0, 0.0),
+            crank_length: 100.0,
+            coupler_length: 200.0,
+            rocker_length: 200.0,
+            coupler_point: CouplerPoint::default(),
+            branch: Branch::Open,
+        };
+        assert_eq!(
+            four_bar.solve(std::f64::consts::PI),
+            Err(AssemblyError::TooFar {
+                distance: 1100.0,
+                reach: 400.0
+            })
+        );
+
+        let four_bar = FourBar {
+            rocker_pivot: (150.0, 0.0),
+            coupler_length: 300.0,
+            rocker_length: 100.0,
+            ..four_bar
+        };
+        assert_eq!(
+            four_bar.solve(0.0),
+            Err(AssemblyError::TooClose {
+                distance: 50.0,
+                reach: 200.0
+            })
+        );
+    }
+}
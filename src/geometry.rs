@@ -0,0 +1,15 @@
+//! Small helpers for working with points in the plane of the mechanism.
+
+/// A position in the plane of the mechanism, in millimetres with the
+/// y-axis pointing up.
+pub type Point = (f64, f64);
+
+/// Distance between `p1` and `p2`.
+pub fn length(p1: Point, p2: Point) -> f64 {
+    ((p2.0 - p1.0).powf(2.0) + (p2.1 - p1.1).powf(2.0)).sqrt()
+}
+
+/// Direction of `p1` as seen from `p2`, counter-clockwise from the x-axis.
+pub fn angle(p1: Point, p2: Point) -> f64 {
+    (p1.1 - p2.1).atan2(p1.0 - p2.0)
+}
@@ -0,0 +1,132 @@
+//! Grashof classification of four-bar linkages.
+
+use std::fmt;
+
+use crate::four_bar::{FourBar, Link};
+
+/// The kind of motion a four-bar performs, as determined by its link lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkageType {
+    /// Grashof, shortest link is the crank: the crank turns fully, the
+    /// rocker oscillates.
+    CrankRocker,
+    /// Grashof, shortest link is the rocker: the rocker turns fully, the
+    /// driven crank oscillates.
+    RockerCrank,
+    /// Grashof, shortest link is the ground: both crank and rocker turn fully.
+    DoubleCrank,
+    /// Grashof, shortest link is the coupler: neither crank nor rocker turns
+    /// fully, but the coupler does.
+    DoubleRocker,
+    /// Shortest plus longest link equals the sum of the other two; the
+    /// linkage can flip between branches at its collinear positions.
+    ChangePoint,
+    /// Non-Grashof: no link turns fully relative to any other.
+    TripleRocker,
+}
+
+impl LinkageType {
+    /// Whether at least one link can turn fully relative to the others.
+    pub fn is_grashof(self) -> bool {
+        !matches!(self, LinkageType::ChangePoint | LinkageType::TripleRocker)
+    }
+}
+
+impl fmt::Display for LinkageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LinkageType::CrankRocker => "crank-rocker",
+            LinkageType::RockerCrank => "rocker-crank",
+            LinkageType::DoubleCrank => "double-crank (drag link)",
+            LinkageType::DoubleRocker => "double-rocker",
+            LinkageType::ChangePoint => "change-point",
+            LinkageType::TripleRocker => "triple-rocker (non-Grashof)",
+        })
+    }
+}
+
+impl FourBar {
+    /// Classifies the linkage by Grashof's criterion.
+    pub fn linkage_type(&self) -> LinkageType {
+        let lengths = Link::ALL.map(|link| (link, self.length(link)));
+        let (shortest, s) = lengths
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        let l = lengths.iter().map(|(_, l)| *l).fold(0.0, f64::max);
+        let p_plus_q = lengths.iter().map(|(_, l)| *l).sum::<f64>() - s - l;
+
+        // treat sums that agree to rounding error as equal, otherwise a
+        // change-point linkage could never be entered by hand
+        if (s + l - p_plus_q).abs() <= 1e-9 * l {
+            LinkageType::ChangePoint
+        } else if s + l > p_plus_q {
+            LinkageType::TripleRocker
+        } else {
+            match shortest {
+                Link::Ground => LinkageType::DoubleCrank,
+                Link::Crank => LinkageType::CrankRocker,
+                Link::Coupler => LinkageType::DoubleRocker,
+                Link::Rocker => LinkageType::RockerCrank,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::four_bar::{Branch, CouplerPoint};
+
+    fn with_lengths(ground: f64, crank: f64, coupler: f64, rocker: f64) -> FourBar {
+        FourBar {
+            crank_pivot: (0.0, 0.0),
+            rocker_pivot: (ground, 0.0),
+            crank_length: crank,
+            coupler_length: coupler,
+            rocker_length: rocker,
+            coupler_point: CouplerPoint::default(),
+            branch: Branch::Open,
+        }
+    }
+
+    #[test]
+    fn classes() {
+        for (four_bar, expected) in [
+            (
+                with_lengths(300.0, 100.0, 250.0, 200.0),
+                LinkageType::CrankRocker,
+            ),
+            (
+                with_lengths(300.0, 200.0, 250.0, 100.0),
+                LinkageType::RockerCrank,
+            ),
+            (
+                with_lengths(100.0, 300.0, 250.0, 200.0),
+                LinkageType::DoubleCrank,
+            ),
+            (
+                with_lengths(300.0, 250.0, 100.0, 200.0),
+                LinkageType::DoubleRocker,
+            ),
+            (
+                with_lengths(100.0, 200.0, 200.0, 100.0),
+                LinkageType::ChangePoint,
+            ),
+            (
+                with_lengths(300.0, 250.0, 260.0, 270.0),
+                LinkageType::TripleRocker,
+            ),
+        ] {
+            let linkage_type = four_bar.linkage_type();
+            assert_eq!(linkage_type, expected);
+            assert_eq!(
+                linkage_type.is_grashof(),
+                !matches!(
+                    expected,
+                    LinkageType::ChangePoint | LinkageType::TripleRocker
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Kinematic solver for planar linkages.
+//!
+//! This crate contains everything needed to solve a mechanism without
+//! pulling in GTK or cairo; the `kinematicsolver` binary is a thin UI on top.
+
+pub mod design;
+pub mod drive;
+pub mod export;
+pub mod four_bar;
+pub mod geometry;
+pub mod grashof;
+pub mod linkage;
+pub mod motion;
+pub mod synthesis;
+pub mod templates;
+pub mod tracer;
+pub mod transmission;
+
+pub use design::{Design, DesignError, DisplaySettings};
+pub use four_bar::{AssemblyError, Branch, CouplerPoint, FourBar, Joint, Link, Pose};
+pub use geometry::Point;
+pub use grashof::LinkageType;
+pub use linkage::{Crank, Linkage, Slider, SolveError};
+pub use motion::{LinkMotion, Motion, PointMotion};
+pub use synthesis::{
+    CouplerPosition, MotionChoices, MotionSynthesis, PathSynthesis, SynthesisError,
+};
+pub use templates::Template;
+pub use tracer::Tracer;
+pub use transmission::TransmissionAngles;
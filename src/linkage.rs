@@ -0,0 +1,803 @@
+//! Position analysis of planar linkages with any number of bodies.
+//!
+//! A [`Linkage`] is stored in one assembled position, the same way a
+//! [`Pose`] stores a four-bar: where every joint is, which bodies it
+//! belongs to and which bodies slide along each other. The dimensions are
+//! whatever that position shows. Other positions are found by
+//! Newton–Raphson on the loop-closure constraints, starting from a nearby
+//! position so that the linkage stays on its branch.
+//! Four-bars are recognised and solved in closed form by [`FourBar`]
+//! instead.
+
+use std::f64::consts::PI;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::four_bar::{AssemblyError, FourBar, Joint, Pose};
+use crate::geometry::{angle, length, Point};
+
+/// Index of the ground body, which never moves.
+pub const GROUND: usize = 0;
+
+/// Newton–Raphson iterations before a position counts as unreachable.
+const MAX_ITERATIONS: usize = 50;
+/// Times a Newton step is halved when it does not reduce the violation.
+const MAX_STEP_HALVINGS: usize = 20;
+/// Largest change of a crank angle, in radians, solved in one go. Larger
+/// changes are made in several steps, so that every step starts close to
+/// its solution and does not jump to another branch.
+const MAX_CRANK_STEP: f64 = PI / 36.0;
+/// Largest violation of a constraint, relative to the size of the linkage,
+/// that still counts as assembled.
+const TOLERANCE: f64 = 1e-10;
+
+/// A body turned by an input about one of its ground joints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crank {
+    /// The driven body.
+    pub body: usize,
+    /// The joint about which the body turns.
+    pub pivot: usize,
+    /// Another joint on the body. Its direction from the pivot is the crank
+    /// angle.
+    pub pin: usize,
+}
+
+/// A prismatic joint: a block sliding along a straight rail on another body.
+///
+/// The block keeps its orientation relative to the rail, and its joint
+/// keeps the distance from the rail line it has in the stored position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slider {
+    /// The body carrying the rail.
+    pub guide: usize,
+    /// Two joints on the guide that the rail runs through, also the ends of
+    /// the rail as drawn.
+    pub rail: (usize, usize),
+    /// The sliding body.
+    pub block: usize,
+    /// The joint on the block riding on the rail.
+    pub joint: usize,
+}
+
+/// A planar linkage of rigid bodies joined by revolute and prismatic
+/// joints, in one assembled position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Linkage {
+    /// Position of every joint, in millimetres.
+    pub joints: Vec<Point>,
+    /// The joints on every body, by index into `joints`; the first body is
+    /// the ground. A joint on several bodies pins them together, a joint on
+    /// a single body is a point carried along by it.
+    pub bodies: Vec<Vec<usize>>,
+    /// Pairs of bodies sliding along each other.
+    #[serde(default)]
+    pub sliders: Vec<Slider>,
+    /// The inputs, in the order their angles are passed to
+    /// [`Linkage::solve`].
+    pub cranks: Vec<Crank>,
+}
+
+/// Returned when a linkage cannot be solved for the given inputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolveError {
+    /// A different number of crank angles than the linkage has cranks.
+    WrongInputCount { expected: usize, found: usize },
+    /// A crank angle that is infinite or not a number.
+    NonFiniteAngle(f64),
+    /// A four-bar that cannot be assembled at the crank angle.
+    FourBar(AssemblyError),
+    /// Newton–Raphson found no position near the starting one satisfying
+    /// all constraints.
+    NoConvergence {
+        /// Largest remaining violation of a constraint, in millimetres.
+        residual: f64,
+    },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::WrongInputCount { expected, found } => write!(
+                f,
+                "the linkage has {expected} cranks, but {found} crank angles were given"
+            ),
+            SolveError::NonFiniteAngle(angle) => {
+                write!(f, "the crank angle {angle} is not a finite number")
+            }
+            SolveError::FourBar(err) => err.fmt(f),
+            SolveError::NoConvergence { residual } => write!(
+                f,
+                "cannot assemble: the joints stay up to {residual:.3} mm apart"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<AssemblyError> for SolveError {
+    fn from(err: AssemblyError) -> Self {
+        SolveError::FourBar(err)
+    }
+}
+
+/// Where a body is: the position of its reference joint and how far it has
+/// turned from the stored position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    origin: Point,
+    rotation: f64,
+}
+
+/// Indices of the parts of a linkage that is a plain four-bar.
+#[derive(Debug, Clone, Copy)]
+struct FourBarParts {
+    crank_pivot: usize,
+    crank_pin: usize,
+    rocker_pin: usize,
+    rocker_pivot: usize,
+    crank: usize,
+    coupler: usize,
+    rocker: usize,
+}
+
+impl FourBarParts {
+    fn pose(&self, joints: &[Point]) -> Pose {
+        Pose {
+            crank_pivot: joints[self.crank_pivot],
+            crank_pin: joints[self.crank_pin],
+            rocker_pin: joints[self.rocker_pin],
+            rocker_pivot: joints[self.rocker_pivot],
+            // only the pins matter for the closed form
+            coupler_point: joints[self.rocker_pin],
+        }
+    }
+}
+
+/// `a` wrapped into -π..π.
+fn wrap(a: f64) -> f64 {
+    (a + PI).rem_euclid(2.0 * PI) - PI
+}
+
+impl Linkage {
+    /// The four-bar shown in `pose`, with the joints in the order of
+    /// [`Joint::ALL`] and the ground, crank, coupler and rocker as bodies.
+    pub fn from_pose(pose: &Pose) -> Self {
+        let [crank_pivot, crank_pin, rocker_pin, rocker_pivot, coupler_point] = [0, 1, 2, 3, 4];
+        Linkage {
+            joints: Joint::ALL.iter().map(|&joint| pose.joint(joint)).collect(),
+            bodies: vec![
+                vec![crank_pivot, rocker_pivot],
+                vec![crank_pivot, crank_pin],
+                vec![crank_pin, rocker_pin, coupler_point],
+                vec![rocker_pivot, rocker_pin],
+            ],
+            sliders: Vec::new(),
+            cranks: vec![Crank {
+                body: 1,
+                pivot: crank_pivot,
+                pin: crank_pin,
+            }],
+        }
+    }
+
+    /// Current angle of every crank, measured counter-clockwise from the
+    /// x-axis.
+    pub fn crank_angles(&self) -> Vec<f64> {
+        self.cranks
+            .iter()
+            .map(|crank| angle(self.joints[crank.pin], self.joints[crank.pivot]))
+            .collect()
+    }
+
+    /// Degrees of freedom by the Grübler–Kutzbach criterion: three per
+    /// moving body, less two for every pair of bodies pinned together or
+    /// sliding along each other.
+    ///
+    /// A linkage that can be driven by its cranks alone has as many degrees
+    /// of freedom as cranks.
+    pub fn mobility(&self) -> i64 {
+        let pins: usize = self
+            .owners()
+            .iter()
+            .map(|owners| owners.len().saturating_sub(1))
+            .sum();
+        3 * (self.bodies.len() as i64 - 1) - 2 * (pins + self.sliders.len()) as i64
+    }
+
+    /// Solves the position of every joint for the given crank angles,
+    /// starting from the stored position.
+    pub fn solve(&self, crank_angles: &[f64]) -> Result<Linkage, SolveError> {
+        self.solve_from(self, crank_angles)
+    }
+
+    /// Solves the position of every joint for the given crank angles, with
+    /// the dimensions of this linkage but starting from the position of
+    /// `start`, which must have the same joints and bodies.
+    ///
+    /// Solving every frame of an animation from the previous one keeps the
+    /// linkage on the same branch, while the dimensions cannot drift.
+    pub fn solve_from(&self, start: &Linkage, crank_angles: &[f64]) -> Result<Linkage, SolveError> {
+        if crank_angles.len() != self.cranks.len() {
+            return Err(SolveError::WrongInputCount {
+                expected: self.cranks.len(),
+                found: crank_angles.len(),
+            });
+        }
+        if let Some(&angle) = crank_angles.iter().find(|angle| !angle.is_finite()) {
+            return Err(SolveError::NonFiniteAngle(angle));
+        }
+        if let Some(parts) = self.four_bar_parts() {
+            return self.solve_four_bar(&parts, start, crank_angles[0]);
+        }
+
+        let mut placements: Vec<Placement> = (0..self.bodies.len())
+            .map(|body| self.placement_at(body, &start.joints))
+            .collect();
+        placements[GROUND] = self.placement_at(GROUND, &self.joints);
+        for slider in &self.sliders {
+            // a block on a single joint shows nothing of its rotation, which
+            // is the rotation of its rail
+            if self.bodies[slider.block].len() < 2 {
+                placements[slider.block].rotation = placements[slider.guide].rotation;
+            }
+        }
+
+        let from = start.crank_angles();
+        let turns: Vec<f64> = crank_angles
+            .iter()
+            .zip(&from)
+            .map(|(&to, &from)| wrap(to - from))
+            .collect();
+        let largest = turns.iter().fold(0.0, |max: f64, turn| max.max(turn.abs()));
+        let steps = (largest / MAX_CRANK_STEP).ceil().max(1.0) as usize;
+        for step in 1..=steps {
+            let angles: Vec<f64> = from
+                .iter()
+                .zip(&turns)
+                .map(|(&from, &turn)| from + turn * step as f64 / steps as f64)
+                .collect();
+            placements = self.newton(placements, &angles)?;
+        }
+
+        Ok(self.place(&placements))
+    }
+
+    /// Solves the linkage at `steps + 1` evenly spaced angles of the first
+    /// crank covering one revolution from its stored angle, both ends
+    /// included, with all other cranks held.
+    ///
+    /// Every position starts from the last one that could be solved.
+    pub fn revolution(&self, steps: usize) -> Vec<(f64, Result<Linkage, SolveError>)> {
+        let mut angles = self.crank_angles();
+        let Some(&first) = angles.first() else {
+            return Vec::new();
+        };
+
+        let mut last = self.clone();
+        (0..=steps)
+            .map(|i| {
+                angles[0] = first + 2.0 * PI * i as f64 / steps as f64;
+                let solved = self.solve_from(&last, &angles);
+                if let Ok(linkage) = &solved {
+                    last = linkage.clone();
+                }
+                (angles[0], solved)
+            })
+            .collect()
+    }
+
+    /// The bodies every joint belongs to.
+    pub fn owners(&self) -> Vec<Vec<usize>> {
+        let mut owners = vec![Vec::new(); self.joints.len()];
+        for (body, joints) in self.bodies.iter().enumerate() {
+            for &joint in joints {
+                owners[joint].push(body);
+            }
+        }
+        owners
+    }
+
+    /// Recognises a linkage that is a plain four-bar driven by its crank.
+    fn four_bar_parts(&self) -> Option<FourBarParts> {
+        if self.bodies.len() != 4 || self.cranks.len() != 1 || !self.sliders.is_empty() {
+            return None;
+        }
+        let owners = self.owners();
+        let pins: Vec<usize> = (0..self.joints.len())
+            .filter(|&joint| owners[joint].len() > 1)
+            .collect();
+        if pins.len() != 4 || pins.iter().any(|&pin| owners[pin].len() != 2) {
+            return None;
+        }
+        // the body across `pin` from `body`, and the other pin of `body`
+        let across = |pin: usize, body: usize| owners[pin].iter().copied().find(|&b| b != body);
+        let other_pin = |body: usize, pin: usize| {
+            pins.iter()
+                .copied()
+                .find(|&p| p != pin && owners[p].contains(&body))
+        };
+
+        let Crank {
+            body: crank,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        } = self.cranks[0];
+        if across(crank_pivot, crank) != Some(GROUND) || owners[crank_pin].len() != 2 {
+            return None;
+        }
+        let coupler = across(crank_pin, crank)?;
+        let rocker_pin = other_pin(coupler, crank_pin)?;
+        let rocker = across(rocker_pin, coupler)?;
+        let rocker_pivot = other_pin(rocker, rocker_pin)?;
+        if crank == GROUND
+            || coupler == GROUND
+            || rocker == GROUND
+            || rocker == crank
+            || across(rocker_pivot, rocker) != Some(GROUND)
+        {
+            return None;
+        }
+
+        Some(FourBarParts {
+            crank_pivot,
+            crank_pin,
+            rocker_pin,
+            rocker_pivot,
+            crank,
+            coupler,
+            rocker,
+        })
+    }
+
+    /// The closed-form fast path, on the branch `start` is assembled in.
+    fn solve_four_bar(
+        &self,
+        parts: &FourBarParts,
+        start: &Linkage,
+        crank_angle: f64,
+    ) -> Result<Linkage, SolveError> {
+        let four_bar = FourBar {
+            branch: FourBar::from_pose(&parts.pose(&start.joints)).branch,
+            ..FourBar::from_pose(&parts.pose(&self.joints))
+        };
+        let pose = four_bar.solve(crank_angle)?;
+
+        let mut joints = self.joints.clone();
+        joints[parts.crank_pin] = pose.crank_pin;
+        joints[parts.rocker_pin] = pose.rocker_pin;
+        let mut placements = vec![self.placement_at(GROUND, &self.joints); self.bodies.len()];
+        for (body, a, b) in [
+            (parts.crank, parts.crank_pivot, parts.crank_pin),
+            (parts.coupler, parts.crank_pin, parts.rocker_pin),
+            (parts.rocker, parts.rocker_pivot, parts.rocker_pin),
+        ] {
+            placements[body] = self.placement_through(body, (a, joints[a]), (b, joints[b]));
+        }
+
+        Ok(self.place(&placements))
+    }
+
+    /// Reference joint of `body`, whose position a placement gives.
+    fn reference(&self, body: usize) -> Point {
+        self.bodies[body]
+            .first()
+            .map_or((0.0, 0.0), |&joint| self.joints[joint])
+    }
+
+    /// Placement of `body` that puts joint `a.0` at `a.1` and turns the
+    /// body so that joint `b.0` lies in the direction of `b.1`.
+    fn placement_through(&self, body: usize, a: (usize, Point), b: (usize, Point)) -> Placement {
+        let rotation = if a.0 == b.0 {
+            0.0
+        } else {
+            angle(b.1, a.1) - angle(self.joints[b.0], self.joints[a.0])
+        };
+        let placement = Placement {
+            origin: a.1,
+            rotation,
+        };
+        // `a` is not necessarily the reference joint
+        let offset = self.transform_from(placement, self.joints[a.0], self.reference(body));
+        Placement {
+            origin: offset,
+            rotation,
+        }
+    }
+
+    /// Placement of `body` with its joints where `joints` has them.
+    fn placement_at(&self, body: usize, joints: &[Point]) -> Placement {
+        match self.bodies[body].as_slice() {
+            [] => Placement {
+                origin: (0.0, 0.0),
+                rotation: 0.0,
+            },
+            &[a] => Placement {
+                origin: joints[a],
+                rotation: 0.0,
+            },
+            &[a, b, ..] => self.placement_through(body, (a, joints[a]), (b, joints[b])),
+        }
+    }
+
+    /// Where stored point `p` goes when the body moves so that its stored
+    /// point `from` ends up at `placement.origin`, turned by
+    /// `placement.rotation`.
+    fn transform_from(&self, placement: Placement, from: Point, p: Point) -> Point {
+        let (sin, cos) = placement.rotation.sin_cos();
+        let local = (p.0 - from.0, p.1 - from.1);
+        (
+            placement.origin.0 + cos * local.0 - sin * local.1,
+            placement.origin.1 + sin * local.0 + cos * local.1,
+        )
+    }
+
+    /// Position of `joint` on `body` placed at `placement`.
+    fn position(&self, placement: Placement, body: usize, joint: usize) -> Point {
+        self.transform_from(placement, self.reference(body), self.joints[joint])
+    }
+
+    /// This linkage with every body moved to its placement.
+    fn place(&self, placements: &[Placement]) -> Linkage {
+        let mut moved = self.clone();
+        for (joint, owners) in self.owners().iter().enumerate() {
+            if let Some(&body) = owners.first() {
+                moved.joints[joint] = self.position(placements[body], body, joint);
+            }
+        }
+        moved
+    }
+
+    /// Largest distance between joints, as the scale of the tolerance.
+    fn size(&self) -> f64 {
+        let mut size: f64 = 1.0;
+        for &a in &self.joints {
+            for &b in &self.joints {
+                size = size.max(length(a, b));
+            }
+        }
+        size
+    }
+
+    /// Violation of every constraint at `placements`, and its derivative
+    /// with respect to the placement of every moving body, three columns
+    /// per body.
+    fn constraints(
+        &self,
+        placements: &[Placement],
+        crank_angles: &[f64],
+    ) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let columns = 3 * (self.bodies.len() - 1);
+        let mut residuals = Vec::new();
+        let mut jacobian = Vec::new();
+
+        // adds the derivative of the joint's position on `body` to `rows`
+        let add = |mut rows: [Vec<f64>; 2], body: usize, sign: f64, joint: usize| {
+            if body != GROUND {
+                let p = self.position(placements[body], body, joint);
+                let arm = (
+                    p.0 - placements[body].origin.0,
+                    p.1 - placements[body].origin.1,
+                );
+                let column = 3 * (body - 1);
+                rows[0][column] += sign;
+                rows[1][column + 1] += sign;
+                rows[0][column + 2] -= sign * arm.1;
+                rows[1][column + 2] += sign * arm.0;
+            }
+            rows
+        };
+
+        for (joint, owners) in self.owners().iter().enumerate() {
+            let Some((&first, others)) = owners.split_first() else {
+                continue;
+            };
+            for &body in others {
+                // the joint is in the same place on both bodies
+                let p = self.position(placements[body], body, joint);
+                let q = self.position(placements[first], first, joint);
+                residuals.push(p.0 - q.0);
+                residuals.push(p.1 - q.1);
+                let rows = [vec![0.0; columns], vec![0.0; columns]];
+                let rows = add(rows, body, 1.0, joint);
+                let [x, y] = add(rows, first, -1.0, joint);
+                jacobian.push(x);
+                jacobian.push(y);
+            }
+        }
+
+        for (crank, &target) in self.cranks.iter().zip(crank_angles) {
+            // scaled by the crank length, so that it weighs like the joints
+            let arm = length(self.joints[crank.pin], self.joints[crank.pivot]).max(1.0);
+            let stored = angle(self.joints[crank.pin], self.joints[crank.pivot]);
+            let rotation = placements[crank.body].rotation;
+            residuals.push(arm * wrap(stored + rotation - target));
+            let mut row = vec![0.0; columns];
+            if crank.body != GROUND {
+                row[3 * (crank.body - 1) + 2] = arm;
+            }
+            jacobian.push(row);
+        }
+
+        for slider in &self.sliders {
+            let (start, end) = slider.rail;
+            let stored_rail = (
+                self.joints[end].0 - self.joints[start].0,
+                self.joints[end].1 - self.joints[start].1,
+            );
+            let rail_length = stored_rail.0.hypot(stored_rail.1).max(1e-9);
+            let stored_joint = (
+                self.joints[slider.joint].0 - self.joints[start].0,
+                self.joints[slider.joint].1 - self.joints[start].1,
+            );
+            let offset =
+                (stored_rail.0 * stored_joint.1 - stored_rail.1 * stored_joint.0) / rail_length;
+
+            let guide = placements[slider.guide];
+            let block = placements[slider.block];
+            let rail_start = self.position(guide, slider.guide, start);
+            let rail_end = self.position(guide, slider.guide, end);
+            let e = (rail_end.0 - rail_start.0, rail_end.1 - rail_start.1);
+            let q = self.position(block, slider.block, slider.joint);
+            let d = (q.0 - rail_start.0, q.1 - rail_start.1);
+
+            // the block joint stays at its distance from the rail line
+            residuals.push((e.0 * d.1 - e.1 * d.0) / rail_length - offset);
+            let mut row = vec![0.0; columns];
+            if slider.block != GROUND {
+                let column = 3 * (slider.block - 1);
+                let arm = (q.0 - block.origin.0, q.1 - block.origin.1);
+                row[column] -= e.1 / rail_length;
+                row[column + 1] += e.0 / rail_length;
+                row[column + 2] += (e.0 * arm.0 + e.1 * arm.1) / rail_length;
+            }
+            if slider.guide != GROUND {
+                let column = 3 * (slider.guide - 1);
+                let arm = (q.0 - guide.origin.0, q.1 - guide.origin.1);
+                row[column] += e.1 / rail_length;
+                row[column + 1] -= e.0 / rail_length;
+                row[column + 2] -= (e.0 * arm.0 + e.1 * arm.1) / rail_length;
+            }
+            jacobian.push(row);
+
+            // and turns with the rail, scaled like the crank angles
+            residuals.push(rail_length * wrap(block.rotation - guide.rotation));
+            let mut row = vec![0.0; columns];
+            if slider.block != GROUND {
+                row[3 * (slider.block - 1) + 2] += rail_length;
+            }
+            if slider.guide != GROUND {
+                row[3 * (slider.guide - 1) + 2] -= rail_length;
+            }
+            jacobian.push(row);
+        }
+
+        (residuals, jacobian)
+    }
+
+    /// Moves the bodies from `placements` until all constraints hold at
+    /// `crank_angles`, by damped Newton–Raphson steps.
+    fn newton(
+        &self,
+        mut placements: Vec<Placement>,
+        crank_angles: &[f64],
+    ) -> Result<Vec<Placement>, SolveError> {
+        let tolerance = TOLERANCE * self.size();
+        // unlike f64::max, a NaN residual is kept, so that it never passes
+        // for assembled
+        let largest = |residuals: &[f64]| {
+            residuals.iter().fold(0.0, |max: f64, r| {
+                if max.is_nan() || r.abs() <= max {
+                    max
+                } else {
+                    r.abs()
+                }
+            })
+        };
+        let squared = |residuals: &[f64]| residuals.iter().map(|r| r * r).sum::<f64>();
+
+        let (mut residuals, mut jacobian) = self.constraints(&placements, crank_angles);
+        for _ in 0..MAX_ITERATIONS {
+            if largest(&residuals) <= tolerance {
+                return Ok(placements);
+            }
+            let Some(step) = least_squares_step(&jacobian, &residuals) else {
+                break;
+            };
+
+            let mut scale = 1.0;
+            let mut improved = false;
+            for _ in 0..MAX_STEP_HALVINGS {
+                let mut tried = placements.clone();
+                for (body, placement) in tried.iter_mut().enumerate().skip(1) {
+                    let column = 3 * (body - 1);
+                    placement.origin.0 += scale * step[column];
+                    placement.origin.1 += scale * step[column + 1];
+                    placement.rotation += scale * step[column + 2];
+                }
+                let (tried_residuals, tried_jacobian) = self.constraints(&tried, crank_angles);
+                if squared(&tried_residuals) < squared(&residuals) {
+                    (placements, residuals, jacobian) = (tried, tried_residuals, tried_jacobian);
+                    improved = true;
+                    break;
+                }
+                scale /= 2.0;
+            }
+            if !improved {
+                break;
+            }
+        }
+
+        if largest(&residuals) <= tolerance {
+            Ok(placements)
+        } else {
+            Err(SolveError::NoConvergence {
+                residual: largest(&residuals),
+            })
+        }
+    }
+}
+
+/// The step `x` minimising |J x + r|, from the normal equations with a
+/// little damping, so that redundant constraints and bodies free to move
+/// without changing anything still give a step.
+pub(crate) fn least_squares_step(jacobian: &[Vec<f64>], residuals: &[f64]) -> Option<Vec<f64>> {
+    let n = jacobian.first().map_or(0, Vec::len);
+    let mut a = vec![vec![0.0; n + 1]; n];
+    for (row, &r) in jacobian.iter().zip(residuals) {
+        for i in 0..n {
+            for j in 0..n {
+                a[i][j] += row[i] * row[j];
+            }
+            a[i][n] -= row[i] * r;
+        }
+    }
+    let damping = 1e-12 * (0..n).fold(1.0, |max: f64, i| max.max(a[i][i]));
+    for (i, row) in a.iter_mut().enumerate() {
+        row[i] += damping;
+    }
+
+    // Gaussian elimination with partial pivoting
+    for i in 0..n {
+        let pivot = (i..n).max_by(|&p, &q| a[p][i].abs().total_cmp(&a[q][i].abs()))?;
+        if a[pivot][i] == 0.0 {
+            return None;
+        }
+        a.swap(i, pivot);
+        let (done, rest) = a.split_at_mut(i + 1);
+        let pivot_row = &done[i];
+        for row in rest {
+            let factor = row[i] / pivot_row[i];
+            for (value, pivot_value) in row[i..].iter_mut().zip(&pivot_row[i..]) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let sum: f64 = (i + 1..n).map(|j| a[i][j] * x[j]).sum();
+        x[i] = (a[i][n] - sum) / a[i][i];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::Template;
+
+    /// Steps per revolution, as many as the drawing uses.
+    const STEPS: usize = 360;
+
+    /// Every template as a general linkage, the four-bar included.
+    fn templates() -> Vec<(Template, Linkage)> {
+        Template::ALL
+            .into_iter()
+            .map(|template| {
+                let design = template.design();
+                let linkage = design
+                    .linkage
+                    .unwrap_or_else(|| Linkage::from_pose(&design.pose));
+                (template, linkage)
+            })
+            .collect()
+    }
+
+    /// Signed distance of the block joint of `slider` from its rail line.
+    fn rail_offset(linkage: &Linkage, slider: &Slider) -> f64 {
+        let (a, b) = (linkage.joints[slider.rail.0], linkage.joints[slider.rail.1]);
+        let q = linkage.joints[slider.joint];
+        ((b.0 - a.0) * (q.1 - a.1) - (b.1 - a.1) * (q.0 - a.0)) / length(a, b)
+    }
+
+    #[test]
+    fn templates_turn_fully_keeping_their_dimensions() {
+        for (template, linkage) in templates() {
+            let tolerance = 1e-6 * linkage.size();
+            let revolution = linkage.revolution(STEPS);
+            assert_eq!(revolution.len(), STEPS + 1);
+            for (crank_angle, solved) in &revolution {
+                let solved = solved
+                    .as_ref()
+                    .unwrap_or_else(|err| panic!("{template:?} at {crank_angle}: {err}"));
+                for joints in &linkage.bodies {
+                    for &a in joints {
+                        for &b in joints {
+                            let stored = length(linkage.joints[a], linkage.joints[b]);
+                            let now = length(solved.joints[a], solved.joints[b]);
+                            assert!((now - stored).abs() < tolerance, "{template:?}");
+                        }
+                    }
+                }
+                for slider in &linkage.sliders {
+                    let offset = rail_offset(solved, slider) - rail_offset(&linkage, slider);
+                    assert!(offset.abs() < tolerance, "{template:?}");
+                }
+                let turned = wrap(solved.crank_angles()[0] - crank_angle);
+                assert!(turned.abs() < 1e-9, "{template:?}");
+            }
+
+            // and come back to where they started
+            let last = revolution[STEPS].1.as_ref().unwrap();
+            for (a, b) in last.joints.iter().zip(&linkage.joints) {
+                assert!(length(*a, *b) < tolerance, "{template:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn jacobian_matches_finite_differences() {
+        const H: f64 = 1e-6;
+        for (template, linkage) in templates() {
+            // away from the stored position, where the rotations are zero
+            let placements: Vec<Placement> = (0..linkage.bodies.len())
+                .map(|body| {
+                    let mut placement = linkage.placement_at(body, &linkage.joints);
+                    if body != GROUND {
+                        placement.origin.0 += 3.0 * body as f64;
+                        placement.origin.1 -= 2.0 * body as f64;
+                        placement.rotation += 0.1 * body as f64;
+                    }
+                    placement
+                })
+                .collect();
+            let angles: Vec<f64> = linkage.crank_angles().iter().map(|a| a + 0.3).collect();
+            let (residuals, jacobian) = linkage.constraints(&placements, &angles);
+
+            for body in 1..linkage.bodies.len() {
+                for k in 0..3 {
+                    let mut moved = placements.clone();
+                    match k {
+                        0 => moved[body].origin.0 += H,
+                        1 => moved[body].origin.1 += H,
+                        _ => moved[body].rotation += H,
+                    }
+                    let (moved_residuals, _) = linkage.constraints(&moved, &angles);
+                    let column = 3 * (body - 1) + k;
+                    for (row, (a, b)) in moved_residuals.iter().zip(&residuals).enumerate() {
+                        let numeric = (a - b) / H;
+                        let analytic = jacobian[row][column];
+                        assert!(
+                            (numeric - analytic).abs() < 1e-3 * (1.0 + analytic.abs()),
+                            "{template:?} row {row} column {column}: {numeric} != {analytic}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_crank_angles_are_refused() {
+        for (_, linkage) in templates() {
+            for angle in [f64::NAN, f64::INFINITY] {
+                assert!(matches!(
+                    linkage.solve(&[angle]),
+                    Err(SolveError::NonFiniteAngle(_))
+                ));
+            }
+        }
+    }
+}
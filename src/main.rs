@@ -1,16 +1,42 @@
-use std::sync::Mutex;
+use std::cell::RefCell;
+use std::f64::consts::{PI, TAU};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::rc::Rc;
 
-use cairo::Context;
-use gtk::{glib, Application, ApplicationWindow, DrawingArea};
-use gtk::{prelude::*, Button, Grid, ToggleButton};
-use once_cell::sync::Lazy;
+use gtk::{gio, glib, Application, ApplicationWindow, DrawingArea, FileChooserAction};
+use gtk::{prelude::*, CheckButton, DropDown, Grid, Label, Orientation, SpinButton, ToggleButton};
+use kinematicsolver::geometry::{angle, length};
+use kinematicsolver::{export, synthesis};
+use kinematicsolver::{
+    Branch, CouplerPosition, Design, FourBar, Joint, Motion, Point, Pose, Template,
+};
+
+mod app;
+
+use app::draw::{
+    draw_coupler_positions, draw_mechanism, draw_precision_points, COUPLER_CURVE_RESOLUTION,
+    JOINT_RADIUS,
+};
+use app::editor::{Edit, PropertyEditor};
+use app::history::Command;
+use app::state::{Animation, AppState, DragMode, PositionDrag};
+use app::tracers::{moving_links, new_tracer, TracerEdit, TracerPanel};
+use app::view::View;
 
 const APP_ID: &str = "org.gtk_rs.HelloWorld2";
 
 fn main() -> glib::ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = app::cli::run(&args) {
+        return code;
+    }
+
     // Create a new application
     let app = Application::builder().application_id(APP_ID).build();
 
+    app.connect_startup(setup_app);
     // Connect to "activate" signal of `app`
     app.connect_activate(build_ui);
 
@@ -18,214 +44,172 @@ fn main() -> glib::ExitCode {
     app.run()
 }
 
-const JOINT_RADIUS: f64 = 10.0;
-const STROKE_WIDTH: f64 = 1.5;
-const SUPPORT_TRIANGLE_WIDTH: f64 = 50.0;
-const SUPPORT_TRIANGLE_HEIGHT: f64 = 30.0;
-const SUPPORT_BASE_WIDTH: f64 = 70.0;
-const SUPPORT_LINE_HEIGHT: f64 = 20.0;
-const SUPPORT_LINE_WIDTH: f64 = 5.0;
-const SUPPORT_LINE_MARGIN: f64 = 1.0;
-const SUPPORT_LINE_COUNT: usize = 5;
-const COUPLER_CURVE_RESOLUTION: usize = 1000;
-
-static FOUR_BAR: Lazy<Mutex<[(f64, f64); 5]>> = Lazy::new(|| {
-    Mutex::new([
-        (550.0, 350.0),
-        (300.0, 400.0),
-        (350.0, 550.0),
-        (600.0, 600.0),
-        (440.0, 550.0),
-    ])
-});
-static SELECTED_JOINT: Lazy<Mutex<Option<(usize, (f64, f64))>>> = Lazy::new(|| Mutex::new(None));
-static ANIMATE: Lazy<Mutex<Option<[(f64, f64); 5]>>> = Lazy::new(|| Mutex::new(None));
-
-fn draw_support(context: &Context, p: (f64, f64)) {
-    context.save();
-    context.set_line_width(STROKE_WIDTH);
-
-    context.arc(p.0, p.1, JOINT_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
-    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-    context.fill_preserve();
-    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
-    context.stroke();
-
-    context.move_to(
-        p.0 - JOINT_RADIUS / 2.0_f64.sqrt(),
-        p.1 + JOINT_RADIUS / 2.0_f64.sqrt(),
-    );
-    context.line_to(
-        p.0 - SUPPORT_TRIANGLE_WIDTH / 2.0,
-        p.1 + SUPPORT_TRIANGLE_HEIGHT,
-    );
-
-    context.move_to(
-        p.0 + JOINT_RADIUS / 2.0_f64.sqrt(),
-        p.1 + JOINT_RADIUS / 2.0_f64.sqrt(),
-    );
-    context.line_to(
-        p.0 + SUPPORT_TRIANGLE_WIDTH / 2.0,
-        p.1 + SUPPORT_TRIANGLE_HEIGHT,
-    );
+/// How much one step of the mouse wheel zooms in or out.
+const ZOOM_STEP: f64 = 1.2;
+/// Crank angle turned by the single step buttons, in radians.
+const CRANK_STEP: f64 = PI / 180.0;
+/// Longest time the animation advances in one frame, in seconds, so that
+/// it continues smoothly after the window was hidden or busy.
+const MAX_FRAME_TIME: f64 = 0.1;
+/// Largest miss, in millimetres, of a coupler position still counted as
+/// taken up by a synthesised four-bar.
+const MOTION_TOLERANCE: f64 = 1e-3;
 
-    context.move_to(
-        p.0 - SUPPORT_BASE_WIDTH / 2.0,
-        p.1 + SUPPORT_TRIANGLE_HEIGHT,
-    );
-    context.line_to(
-        p.0 + SUPPORT_BASE_WIDTH / 2.0,
-        p.1 + SUPPORT_TRIANGLE_HEIGHT,
-    );
+/// A bold label introducing a section of the status panel.
+fn heading(text: &str) -> Label {
+    Label::builder()
+        .label(format!("<b>{text}</b>"))
+        .use_markup(true)
+        .xalign(0.0)
+        .build()
+}
 
-    for i in 0..SUPPORT_LINE_COUNT {
-        context.move_to(
-            p.0 - SUPPORT_BASE_WIDTH / 2.0
-                + SUPPORT_LINE_MARGIN
-                + (i as f64 / SUPPORT_LINE_COUNT as f64)
-                    * (SUPPORT_BASE_WIDTH - SUPPORT_LINE_MARGIN),
-            p.1 + SUPPORT_TRIANGLE_HEIGHT + SUPPORT_LINE_HEIGHT,
+fn format_motion(motion: &Motion) -> String {
+    let magnitude = |p: (f64, f64)| p.0.hypot(p.1);
+    let mut text = String::new();
+    for (name, link) in [("coupler", motion.coupler), ("rocker", motion.rocker)] {
+        text += &format!(
+            "{name}: ω = {:.3} rad/s, α = {:.3} rad/s²\n",
+            link.omega, link.alpha
         );
-        context.rel_line_to(SUPPORT_LINE_WIDTH, -SUPPORT_LINE_HEIGHT);
     }
-
-    context.stroke();
-    context.restore();
-}
-
-fn draw_joint(context: &Context, p: (f64, f64)) {
-    context.save();
-
-    context.set_line_width(STROKE_WIDTH);
-    context.arc(p.0, p.1, JOINT_RADIUS, 0.0, 2.0 * std::f64::consts::PI);
-    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-    context.fill_preserve();
-    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
-    context.stroke();
-
-    context.restore();
+    for (name, point) in [
+        ("crank pin", motion.crank_pin),
+        ("rocker pin", motion.rocker_pin),
+        ("coupler point", motion.coupler_point),
+    ] {
+        text += &format!(
+            "{name}: |v| = {:.1} mm/s, |a| = {:.1} mm/s²\n",
+            magnitude(point.velocity),
+            magnitude(point.acceleration)
+        );
+    }
+    text.pop();
+    text
 }
 
-fn draw_connecting_line(context: &Context, p1: (f64, f64), p2: (f64, f64)) {
-    context.save();
-
-    context.set_line_width(STROKE_WIDTH);
-    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
-    context.move_to(p1.0, p1.1);
-    context.line_to(p2.0, p2.1);
-    context.stroke();
-
-    context.restore();
+/// Position of `branch` in the branch drop-down.
+fn branch_index(branch: Branch) -> u32 {
+    Branch::ALL.iter().position(|b| *b == branch).unwrap() as u32
 }
 
-fn draw_four_bar_linkage(context: &Context, joints: [(f64, f64); 5]) {
-    for i in 0..4 {
-        draw_connecting_line(context, joints[i], joints[(i + 1) % 4]);
+fn save_design(window: &ApplicationWindow, state: &RefCell<AppState>, path: PathBuf) {
+    let design = state.borrow().design();
+    match design.save(&path) {
+        Ok(()) => state.borrow_mut().current_file = Some(path),
+        Err(err) => app::file::show_error(window, "Could not save design", &err),
     }
-
-    draw_coupler_curve(context, joints);
-
-    draw_joint(context, joints[0]);
-    draw_joint(context, joints[1]);
-    draw_support(context, joints[2]);
-    draw_support(context, joints[3]);
-}
-
-fn length(p1: (f64, f64), p2: (f64, f64)) -> f64 {
-    ((p2.0 - p1.0).powf(2.0) + (p2.1 - p1.1).powf(2.0)).sqrt()
 }
 
-fn angle(p1: (f64, f64), p2: (f64, f64)) -> f64 {
-    -(p1.1 - p2.1).atan2(p1.0 - p2.0)
+/// Applies dragging the handle `drag` of the motion synthesis construction
+/// to where `moved` takes the point it was grabbed at.
+fn drag_position(state: &mut AppState, drag: PositionDrag, moved: impl Fn(Point) -> Point) {
+    let positions = &mut state.coupler_positions;
+    let choices = &mut state.motion_choices;
+    match drag {
+        PositionDrag::Move(index, start) => {
+            if let Some(position) = positions.get_mut(index) {
+                position.point = moved(start);
+            }
+        }
+        PositionDrag::Turn(index, start) => {
+            if let Some(position) = positions.get_mut(index) {
+                let target = moved(start);
+                if target != position.point {
+                    position.angle = angle(target, position.point);
+                }
+            }
+        }
+        PositionDrag::MovingPivot(dyad, start) => {
+            if let Some(first) = positions.first() {
+                choices.moving_pivots[dyad] = first.local(moved(start));
+            }
+        }
+        PositionDrag::GroundPivot(dyad, start) => {
+            let construction = synthesis::dyad_construction(
+                positions,
+                choices.moving_pivots[dyad],
+                choices.bisector_offsets[dyad],
+            );
+            if let Some(&(middle, direction)) = construction
+                .as_ref()
+                .and_then(|construction| construction.bisectors.first())
+            {
+                let target = moved(start);
+                choices.bisector_offsets[dyad] =
+                    (target.0 - middle.0) * direction.0 + (target.1 - middle.1) * direction.1;
+            }
+        }
+    }
 }
 
-fn get_rocker_pos(p1: (f64, f64), joints: [(f64, f64); 5]) -> (f64, f64) {
-    let crank_length = length(joints[1], joints[2]);
-    let rocker_length = length(joints[0], joints[3]);
-    let coupler_length = length(joints[0], joints[1]);
-
-    let p2 = (joints[3].0, joints[3].1);
-    let r = length(p1, p2);
-    (
-        0.5 * (p1.0 + p2.0)
-            + (coupler_length.powf(2.0) - rocker_length.powf(2.0)) / (2.0 * r.powf(2.0))
-                * (p2.0 - p1.0)
-            + 0.5
-                * (2.0 * (coupler_length.powf(2.0) + rocker_length.powf(2.0)) / r.powf(2.0)
-                    - (coupler_length.powf(2.0) - rocker_length.powf(2.0)).powf(2.0) / r.powf(4.0)
-                    - 1.0)
-                    .sqrt()
-                * (p2.1 - p1.1),
-        0.5 * (p1.1 + p2.1)
-            + (coupler_length.powf(2.0) - rocker_length.powf(2.0)) / (2.0 * r.powf(2.0))
-                * (p2.1 - p1.1)
-            + 0.5
-                * (2.0 * (coupler_length.powf(2.0) + rocker_length.powf(2.0)) / r.powf(2.0)
-                    - (coupler_length.powf(2.0) - rocker_length.powf(2.0)).powf(2.0) / r.powf(4.0)
-                    - 1.0)
-                    .sqrt()
-                * (p1.0 - p2.0),
-    )
+/// Reassembles `pose` on `branch`, keeping its crank angle.
+fn set_branch(pose: &mut Pose, branch: Branch) {
+    let four_bar = FourBar {
+        branch,
+        ..FourBar::from_pose(pose)
+    };
+    if let Ok(solved) = four_bar.solve(pose.crank_angle()) {
+        *pose = solved;
+    }
 }
 
-fn draw_coupler_curve(context: &Context, joints: [(f64, f64); 5]) {
-    context.save();
-
-    context.set_line_width(1.5 * STROKE_WIDTH);
-    context.set_source_rgba(1.0, 0.0, 0.0, 0.6);
-
-    context.move_to(joints[4].0, joints[4].1);
-
-    let theta = angle(joints[0], joints[1]);
-    let (dx, dy) = (joints[4].0 - joints[1].0, joints[4].1 - joints[1].1);
-    let crank_length = length(joints[1], joints[2]);
-
-    for i in 0..COUPLER_CURVE_RESOLUTION {
-        let a = std::f64::consts::PI * 2.0 * i as f64 / COUPLER_CURVE_RESOLUTION as f64
-            + angle(joints[1], joints[2]);
-        let p1 = (
-            joints[2].0 + crank_length * a.cos(),
-            joints[2].1 - crank_length * a.sin(),
-        );
-        let p3 = get_rocker_pos(p1, joints);
+/// Sets up what all windows share: the menu bar, its shortcuts and the
+/// application-wide actions.
+fn setup_app(app: &Application) {
+    let new_window = gio::SimpleAction::new("new-window", None);
+    let weak_app = app.downgrade();
+    new_window.connect_activate(move |_, _| {
+        if let Some(app) = weak_app.upgrade() {
+            build_ui(&app);
+        }
+    });
+    app.add_action(&new_window);
 
-        let psi = angle(p3, p1);
-        let p4 = (
-            p1.0 + dx * (theta - psi).cos() - dy * (theta - psi).sin(),
-            p1.1 + dy * (theta - psi).cos() + dx * (theta - psi).sin(),
-        );
-        context.line_to(p4.0, p4.1);
-        context.stroke();
-        context.move_to(p4.0, p4.1);
+    let file_menu = gio::Menu::new();
+    file_menu.append(Some("_New Window"), Some("app.new-window"));
+    let template_menu = gio::Menu::new();
+    let (mechanisms, six_bars) = (gio::Menu::new(), gio::Menu::new());
+    for (index, template) in (0u32..).zip(Template::ALL) {
+        let item = gio::MenuItem::new(Some(template.name()), None);
+        item.set_action_and_target_value(Some("win.template"), Some(&index.to_variant()));
+        let section = if template.is_six_bar() {
+            &six_bars
+        } else {
+            &mechanisms
+        };
+        section.append_item(&item);
     }
-
-    context.line_to(joints[4].0, joints[4].1);
-    context.stroke();
-
-    context.set_line_width(STROKE_WIDTH);
-
-    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
-    context.move_to(joints[0].0, joints[0].1);
-    context.line_to(joints[4].0, joints[4].1);
-    context.line_to(joints[1].0, joints[1].1);
-    context.stroke();
-
-    context.arc(
-        joints[4].0,
-        joints[4].1,
-        5.0,
-        0.0,
-        2.0 * std::f64::consts::PI,
-    );
-    context.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-    context.fill_preserve();
-    context.set_source_rgba(0.0, 0.0, 0.0, 0.6);
-    context.stroke();
-
-    context.restore();
+    template_menu.append_section(None, &mechanisms);
+    template_menu.append_section(None, &six_bars);
+    file_menu.append_submenu(Some("New from _Template"), &template_menu);
+    file_menu.append(Some("_Open…"), Some("win.open"));
+    file_menu.append(Some("_Save"), Some("win.save"));
+    file_menu.append(Some("Save _As…"), Some("win.save-as"));
+    let export_menu = gio::Menu::new();
+    export_menu.append(Some("Motion as _CSV…"), Some("win.export-csv"));
+    export_menu.append(Some("Drawing as S_VG…"), Some("win.export-svg"));
+    export_menu.append(Some("Drawing as _PDF…"), Some("win.export-pdf"));
+    file_menu.append_submenu(Some("_Export"), &export_menu);
+    let menubar = gio::Menu::new();
+    menubar.append_submenu(Some("_File"), &file_menu);
+    let edit_menu = gio::Menu::new();
+    edit_menu.append(Some("_Undo"), Some("win.undo"));
+    edit_menu.append(Some("_Redo"), Some("win.redo"));
+    menubar.append_submenu(Some("_Edit"), &edit_menu);
+    app.set_menubar(Some(&menubar));
+    app.set_accels_for_action("app.new-window", &["<Control>n"]);
+    app.set_accels_for_action("win.open", &["<Control>o"]);
+    app.set_accels_for_action("win.save", &["<Control>s"]);
+    app.set_accels_for_action("win.save-as", &["<Control><Shift>s"]);
+    app.set_accels_for_action("win.undo", &["<Control>z"]);
+    app.set_accels_for_action("win.redo", &["<Control><Shift>z"]);
 }
 
+/// Opens a new window with its own mechanism.
 fn build_ui(app: &Application) {
+    let state: Rc<RefCell<AppState>> = Rc::default();
+
     // Create a button with label and margins
     let drawing_area = DrawingArea::builder()
         .margin_top(12)
@@ -236,96 +220,1149 @@ fn build_ui(app: &Application) {
         .content_width(1000)
         .build();
 
-    drawing_area.set_draw_func(|area, context, width, height| {
-        draw_four_bar_linkage(context, *FOUR_BAR.lock().unwrap());
+    drawing_area.set_draw_func({
+        let state = state.clone();
+        move |_, context, _, _| {
+            let mut state = state.borrow_mut();
+            state.update_linkage_paths();
+            state.update_four_bar_paths();
+            let _ = draw_mechanism(
+                context,
+                &state.view,
+                (&state.pose, &state.four_bar_paths),
+                state
+                    .linkage
+                    .as_ref()
+                    .map(|linkage| (linkage, state.linkage_paths.revolution.as_slice())),
+                &state.tracers,
+                &state.display,
+                state.crank_input,
+            );
+            let _ = draw_precision_points(context, &state.view, &state.precision_points);
+            let _ = draw_coupler_positions(
+                context,
+                &state.view,
+                &state.coupler_positions,
+                &state.motion_choices,
+            );
+        }
     });
 
-    let gesture = gtk::GestureDrag::new();
-    gesture.set_button(gtk::gdk::ffi::GDK_BUTTON_PRIMARY as u32);
-    gesture.connect_drag_begin(|_, x, y| {
-        if ANIMATE.lock().unwrap().is_some() {
-            return;
+    let plots = DrawingArea::builder()
+        .margin_top(12)
+        .margin_bottom(12)
+        .content_height(1000)
+        .content_width(500)
+        .build();
+
+    plots.set_draw_func({
+        let state = state.clone();
+        move |_, context, width, height| {
+            let mut state = state.borrow_mut();
+            // the plots are of the four-bar's angles
+            if state.linkage.is_none() {
+                state.update_four_bar_paths();
+                // a failed draw only leaves the plots blank until the next frame
+                let _ = app::plots::draw_plots(
+                    context,
+                    width as f64,
+                    height as f64,
+                    &state.pose,
+                    &state.four_bar_paths,
+                );
+            }
         }
+    });
 
-        for (i, p) in FOUR_BAR.lock().unwrap().iter().enumerate() {
-            if length(*p, (x, y)) < (JOINT_RADIUS + 10.0) {
-                // selecting current joint
-                *SELECTED_JOINT.lock().unwrap() = Some((i, *p));
+    let branch = DropDown::from_strings(&["Open", "Crossed"]);
+    branch.connect_selected_notify({
+        let state = state.clone();
+        move |branch| {
+            let branch = Branch::ALL[branch.selected() as usize];
+            let mut state = state.borrow_mut();
+            // the drop-down is also set to show the branch of a dragged,
+            // edited, loaded or restored pose, which must not be re-solved
+            // and recorded as an edit of its own
+            if FourBar::from_pose(&state.pose).branch == branch {
                 return;
             }
+            let before = state.reference_pose();
+            set_branch(&mut state.pose, branch);
+            if let Some(animation) = state.animation.as_mut() {
+                set_branch(&mut animation.reference, branch);
+                animation.four_bar.branch = branch;
+            }
+            let after = state.reference_pose();
+            state.history.push(Command::Edit { before, after });
         }
-        *SELECTED_JOINT.lock().unwrap() = None;
     });
-    gesture.connect_drag_update(|gesture, x, y| {
-        if ANIMATE.lock().unwrap().is_some() {
-            return;
+
+    let show_both_branches = CheckButton::builder().label("Show both branches").build();
+    show_both_branches.connect_toggled({
+        let state = state.clone();
+        move |button| state.borrow_mut().display.show_both_branches = button.is_active()
+    });
+
+    let gesture = gtk::GestureDrag::new();
+    gesture.set_button(gtk::gdk::ffi::GDK_BUTTON_PRIMARY as u32);
+    gesture.connect_drag_begin({
+        let state = state.clone();
+        move |_, x, y| {
+            let mut state = state.borrow_mut();
+            let state = &mut *state;
+            // precision points are not part of the mechanism, so they can
+            // be placed while it moves
+            if state.drag_mode == DragMode::PlacePoints {
+                let points = &mut state.precision_points;
+                let index = points
+                    .iter()
+                    .position(|&p| length(state.view.to_screen(p), (x, y)) < (JOINT_RADIUS + 10.0))
+                    .unwrap_or_else(|| {
+                        points.push(state.view.to_world((x, y)));
+                        points.len() - 1
+                    });
+                state.point_drag = Some((index, points[index]));
+                return;
+            }
+            if state.drag_mode == DragMode::PlacePositions {
+                state.position_drag = state.position_handle((x, y)).or_else(|| {
+                    // a new position points where the pointer is dragged
+                    let positions = &mut state.coupler_positions;
+                    if positions.len() >= synthesis::MAX_POSITIONS {
+                        return None;
+                    }
+                    let point = state.view.to_world((x, y));
+                    let angle = positions.last().map_or(0.0, |last| last.angle);
+                    positions.push(CouplerPosition { point, angle });
+                    Some(PositionDrag::Turn(positions.len() - 1, point))
+                });
+                return;
+            }
+            if state.animation.is_some() {
+                return;
+            }
+
+            if let Some(linkage) = &state.linkage {
+                // joints of a general linkage can only be moved apart
+                state.linkage_drag = if state.drag_mode == DragMode::EditGeometry {
+                    linkage
+                        .joints
+                        .iter()
+                        .position(|&p| {
+                            length(state.view.to_screen(p), (x, y)) < (JOINT_RADIUS + 10.0)
+                        })
+                        .map(|joint| (joint, linkage.joints[joint], linkage.clone()))
+                } else {
+                    None
+                };
+                return;
+            }
+
+            let pose = state.pose;
+            for joint in Joint::ALL {
+                if state.drag_mode == DragMode::Move && joint.is_ground() {
+                    continue;
+                }
+                let p = pose.joint(joint);
+                if length(state.view.to_screen(p), (x, y)) < (JOINT_RADIUS + 10.0) {
+                    // selecting current joint
+                    state.selected_joint = Some((joint, p));
+                    state.drag_start = Some(pose);
+                    return;
+                }
+            }
+            state.selected_joint = None;
         }
+    });
+    gesture.connect_drag_update({
+        let state = state.clone();
+        let branch_dropdown = branch.clone();
+        move |gesture, x, y| {
+            let current = {
+                let mut state = state.borrow_mut();
+                if let Some((index, p)) = state.point_drag {
+                    let offset = state.view.vector_to_world((x, y));
+                    if let Some(point) = state.precision_points.get_mut(index) {
+                        *point = (p.0 + offset.0, p.1 + offset.1);
+                    }
+                    drop(state);
+                    gesture.widget().queue_draw();
+                    return;
+                }
+                if let Some(drag) = state.position_drag {
+                    let offset = state.view.vector_to_world((x, y));
+                    let moved = |p: (f64, f64)| (p.0 + offset.0, p.1 + offset.1);
+                    drag_position(&mut state, drag, moved);
+                    drop(state);
+                    gesture.widget().queue_draw();
+                    return;
+                }
+                if state.animation.is_some() {
+                    return;
+                }
+                if let Some(&(joint, p, _)) = state.linkage_drag.as_ref() {
+                    let offset = state.view.vector_to_world((x, y));
+                    if let Some(linkage) = state.linkage.as_mut() {
+                        linkage.joints[joint] = (p.0 + offset.0, p.1 + offset.1);
+                    }
+                    drop(state);
+                    gesture.widget().queue_draw();
+                    return;
+                }
+                let Some((joint, p)) = state.selected_joint else {
+                    return;
+                };
 
-        if let Some((joint, p)) = *SELECTED_JOINT.lock().unwrap() {
-            FOUR_BAR.lock().unwrap()[joint] = (p.0 + x, p.1 + y);
+                let offset = state.view.vector_to_world((x, y));
+                let target = (p.0 + offset.0, p.1 + offset.1);
+                match state.drag_mode {
+                    DragMode::EditGeometry => *state.pose.joint_mut(joint) = target,
+                    DragMode::Move => {
+                        // dimensions from the start of the drag, so that
+                        // they cannot drift while following the pointer
+                        let start = state.drag_start.unwrap_or(state.pose);
+                        let four_bar = FourBar::from_pose(&start);
+                        if let Some(moved) = four_bar.drive(joint, target, state.pose.crank_angle())
+                        {
+                            state.pose = moved;
+                        }
+                    }
+                    // no joint is picked up while placing points
+                    DragMode::PlacePoints | DragMode::PlacePositions => {}
+                }
+                FourBar::from_pose(&state.pose).branch
+            };
+            // dragging the rocker pin across the crank pin - rocker pivot
+            // line switches branches
+            branch_dropdown.set_selected(branch_index(current));
             gesture.widget().queue_draw();
         }
     });
-    gesture.connect_drag_end(|_, _, _| {
-        *SELECTED_JOINT.lock().unwrap() = None;
+    gesture.connect_drag_end({
+        let state = state.clone();
+        move |_, _, _| {
+            let mut state = state.borrow_mut();
+            state.selected_joint = None;
+            state.point_drag = None;
+            state.position_drag = None;
+            // the whole drag is undone in one step
+            if let Some(before) = state.drag_start.take() {
+                let after = state.pose;
+                state.history.push(Command::Edit { before, after });
+            }
+            if let Some((_, _, before)) = state.linkage_drag.take() {
+                if let Some(after) = state.linkage.clone() {
+                    state.history.push(Command::EditLinkage { before, after });
+                }
+            }
+        }
     });
 
     drawing_area.add_controller(gesture);
 
+    let pan = gtk::GestureDrag::new();
+    pan.set_button(gtk::gdk::ffi::GDK_BUTTON_MIDDLE as u32);
+    pan.connect_drag_begin({
+        let state = state.clone();
+        move |_, _, _| {
+            let mut state = state.borrow_mut();
+            state.pan_start = state.view.origin;
+        }
+    });
+    pan.connect_drag_update({
+        let state = state.clone();
+        move |gesture, x, y| {
+            {
+                let mut state = state.borrow_mut();
+                state.view.origin = state.pan_start;
+                state.view.pan((x, y));
+            }
+            gesture.widget().queue_draw();
+        }
+    });
+    drawing_area.add_controller(pan);
+
+    let pointer = gtk::EventControllerMotion::new();
+    pointer.connect_motion({
+        let state = state.clone();
+        move |_, x, y| state.borrow_mut().pointer = (x, y)
+    });
+    drawing_area.add_controller(pointer);
+
+    let zoom = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+    zoom.connect_scroll({
+        let state = state.clone();
+        move |zoom, _, dy| {
+            {
+                let mut state = state.borrow_mut();
+                let center = state.pointer;
+                state.view.zoom(ZOOM_STEP.powf(-dy), center);
+            }
+            zoom.widget().queue_draw();
+            glib::Propagation::Stop
+        }
+    });
+    drawing_area.add_controller(zoom);
+
+    let fit = gtk::Button::builder().label("Fit").build();
+    fit.connect_clicked({
+        let state = state.clone();
+        let area = drawing_area.clone();
+        move |_| {
+            {
+                let mut state = state.borrow_mut();
+                let pose = state.reference_pose();
+                let linkage = state.reference_linkage();
+                state.view = View::fit_mechanism(
+                    &pose,
+                    linkage.as_ref(),
+                    area.width() as f64,
+                    area.height() as f64,
+                );
+            }
+            area.queue_draw();
+        }
+    });
+
     let button = ToggleButton::builder().label("Animate").build();
-    button.connect_toggled(|button| {
-        if button.is_active() {
-            *ANIMATE.lock().unwrap() = Some(*FOUR_BAR.lock().unwrap());
-        } else {
-            *FOUR_BAR.lock().unwrap() = ANIMATE.lock().unwrap().unwrap();
-            *ANIMATE.lock().unwrap() = None;
+    button.connect_toggled({
+        let state = state.clone();
+        move |button| {
+            let mut state = state.borrow_mut();
+            if button.is_active() {
+                state.animation = Some(Animation::new(state.pose, state.linkage.clone()));
+            } else if let Some(animation) = state.animation.take() {
+                state.pose = animation.reference;
+                state.linkage = animation.linkage;
+            }
+        }
+    });
+
+    let drag_mode = DropDown::from_strings(&[
+        "Edit geometry",
+        "Move",
+        "Place precision points",
+        "Place coupler positions",
+    ]);
+    drag_mode.set_tooltip_text(Some(
+        "Edit geometry: dragging a joint changes the link lengths\n\
+         Move: dragging a moving joint turns the crank, keeping the link lengths\n\
+         Place precision points: clicking adds a point for path synthesis, dragging moves one\n\
+         Place coupler positions: clicking and dragging adds a position for motion synthesis",
+    ));
+    drag_mode.connect_selected_notify({
+        let state = state.clone();
+        move |drag_mode| {
+            state.borrow_mut().drag_mode = DragMode::ALL[drag_mode.selected() as usize];
+        }
+    });
+
+    let pause = ToggleButton::builder().label("Pause").build();
+    pause.connect_toggled({
+        let state = state.clone();
+        move |pause| state.borrow_mut().paused = pause.is_active()
+    });
+
+    // Stepping pauses the animation, so that the frame stays on screen.
+    let step = |step: f64| {
+        let state = state.clone();
+        let pause = pause.clone();
+        let area = drawing_area.clone();
+        move |_: &gtk::Button| {
+            pause.set_active(true);
+            {
+                let mut state = state.borrow_mut();
+                if let Some(crank_angle) = state.animation.as_ref().map(|a| a.crank_angle) {
+                    state.turn_to(crank_angle + step);
+                }
+            }
+            area.queue_draw();
+        }
+    };
+    let step_back = gtk::Button::builder()
+        .icon_name("media-skip-backward")
+        .tooltip_text("Turn the crank back by 1°")
+        .build();
+    step_back.connect_clicked(step(-CRANK_STEP));
+    let step_forward = gtk::Button::builder()
+        .icon_name("media-skip-forward")
+        .tooltip_text("Turn the crank on by 1°")
+        .build();
+    step_forward.connect_clicked(step(CRANK_STEP));
+
+    let reverse = CheckButton::builder().label("Reverse").build();
+    reverse.connect_toggled({
+        let state = state.clone();
+        move |reverse| state.borrow_mut().reverse = reverse.is_active()
+    });
+
+    let speed = gtk::Scale::with_range(Orientation::Horizontal, 0.0, 120.0, 1.0);
+    speed.set_value(state.borrow().speed_rpm);
+    speed.set_digits(0);
+    speed.set_draw_value(true);
+    speed.set_hexpand(true);
+    speed.connect_value_changed({
+        let state = state.clone();
+        move |speed| state.borrow_mut().speed_rpm = speed.value()
+    });
+
+    let scrubber = gtk::Scale::with_range(Orientation::Horizontal, 0.0, 360.0, 1.0);
+    scrubber.set_digits(1);
+    scrubber.set_draw_value(true);
+    scrubber.set_hexpand(true);
+    // only the user changes the value through this signal, not the tick
+    // callback showing the current crank angle
+    scrubber.connect_change_value({
+        let state = state.clone();
+        let button = button.clone();
+        let pause = pause.clone();
+        let area = drawing_area.clone();
+        move |_, _, degrees| {
+            // scrubbing inspects a paused animation, so that stopping it
+            // brings back the design as it was
+            button.set_active(true);
+            pause.set_active(true);
+            state
+                .borrow_mut()
+                .turn_to(degrees.clamp(0.0, 360.0).to_radians());
+            area.queue_draw();
+            glib::Propagation::Proceed
+        }
+    });
+
+    let controls = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .build();
+    controls.append(&button);
+    controls.append(&drag_mode);
+    controls.append(&branch);
+    controls.append(&show_both_branches);
+    controls.append(&fit);
+
+    let playback = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .build();
+    playback.append(&pause);
+    playback.append(&step_back);
+    playback.append(&step_forward);
+    playback.append(&reverse);
+    playback.append(&Label::new(Some("Speed")));
+    playback.append(&speed);
+    playback.append(&Label::new(Some("RPM")));
+
+    let timeline = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(10)
+        .build();
+    timeline.append(&Label::new(Some("Crank angle")));
+    timeline.append(&scrubber);
+    timeline.append(&Label::new(Some("°")));
+
+    let mechanism_type = Label::builder().xalign(0.0).build();
+    let transmission = Label::builder().xalign(0.0).build();
+    let transmission_warning = Label::builder().xalign(0.0).wrap(true).build();
+
+    let min_transmission_angle = SpinButton::with_range(0.0, 90.0, 1.0);
+    min_transmission_angle.set_value(state.borrow().display.min_transmission_angle);
+    min_transmission_angle.connect_value_changed({
+        let state = state.clone();
+        move |spin| state.borrow_mut().display.min_transmission_angle = spin.value()
+    });
+    let min_transmission_angle_row = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    min_transmission_angle_row.append(&Label::new(Some("Warn below")));
+    min_transmission_angle_row.append(&min_transmission_angle);
+    min_transmission_angle_row.append(&Label::new(Some("°")));
+
+    let motion = Label::builder().xalign(0.0).build();
+
+    let crank_omega = SpinButton::with_range(-100.0, 100.0, 0.1);
+    crank_omega.set_digits(2);
+    crank_omega.set_value(state.borrow().crank_input.omega);
+    crank_omega.connect_value_changed({
+        let state = state.clone();
+        move |spin| state.borrow_mut().crank_input.omega = spin.value()
+    });
+    let crank_alpha = SpinButton::with_range(-1000.0, 1000.0, 0.1);
+    crank_alpha.set_digits(2);
+    crank_alpha.set_value(state.borrow().crank_input.alpha);
+    crank_alpha.connect_value_changed({
+        let state = state.clone();
+        move |spin| state.borrow_mut().crank_input.alpha = spin.value()
+    });
+    let crank_input = Grid::builder().row_spacing(6).column_spacing(6).build();
+    crank_input.attach(&Label::new(Some("Crank ω")), 0, 0, 1, 1);
+    crank_input.attach(&crank_omega, 1, 0, 1, 1);
+    crank_input.attach(&Label::new(Some("rad/s")), 2, 0, 1, 1);
+    crank_input.attach(&Label::new(Some("Crank α")), 0, 1, 1, 1);
+    crank_input.attach(&crank_alpha, 1, 1, 1, 1);
+    crank_input.attach(&Label::new(Some("rad/s²")), 2, 1, 1, 1);
+
+    let show_velocities = CheckButton::builder().label("Show velocities").build();
+    show_velocities.connect_toggled({
+        let state = state.clone();
+        move |button| state.borrow_mut().display.show_velocities = button.is_active()
+    });
+    let show_accelerations = CheckButton::builder().label("Show accelerations").build();
+    show_accelerations.connect_toggled({
+        let state = state.clone();
+        move |button| state.borrow_mut().display.show_accelerations = button.is_active()
+    });
+
+    let synthesise = gtk::Button::builder()
+        .label("Synthesise")
+        .tooltip_text("Replace the mechanism by a four-bar through the precision points")
+        .build();
+    let clear_points = gtk::Button::builder().label("Clear points").build();
+    let synthesis_result = Label::builder().xalign(0.0).wrap(true).build();
+    clear_points.connect_clicked({
+        let state = state.clone();
+        let synthesis_result = synthesis_result.clone();
+        let area = drawing_area.clone();
+        move |_| {
+            state.borrow_mut().precision_points.clear();
+            synthesis_result.set_text("");
+            area.queue_draw();
+        }
+    });
+    let synthesis_buttons = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    synthesis_buttons.append(&synthesise);
+    synthesis_buttons.append(&clear_points);
+
+    let synthesise_motion = gtk::Button::builder()
+        .label("Synthesise")
+        .tooltip_text("Replace the mechanism by the four-bar constructed for the coupler positions")
+        .build();
+    let clear_positions = gtk::Button::builder().label("Clear positions").build();
+    let motion_result = Label::builder().xalign(0.0).wrap(true).build();
+    clear_positions.connect_clicked({
+        let state = state.clone();
+        let motion_result = motion_result.clone();
+        let area = drawing_area.clone();
+        move |_| {
+            state.borrow_mut().coupler_positions.clear();
+            motion_result.set_text("");
+            area.queue_draw();
+        }
+    });
+    let motion_buttons = gtk::Box::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .build();
+    motion_buttons.append(&synthesise_motion);
+    motion_buttons.append(&clear_positions);
+
+    let status = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .margin_end(12)
+        .width_request(300)
+        .build();
+    status.append(&heading("Mechanism"));
+    status.append(&mechanism_type);
+    status.append(&heading("Transmission angle"));
+    status.append(&transmission);
+    status.append(&transmission_warning);
+    status.append(&min_transmission_angle_row);
+    status.append(&heading("Velocity and acceleration"));
+    status.append(&crank_input);
+    status.append(&show_velocities);
+    status.append(&show_accelerations);
+    status.append(&motion);
+    status.append(&heading("Path synthesis"));
+    status.append(
+        &Label::builder()
+            .label(format!(
+                "Click {} or more points in order in \"Place precision points\" mode; \
+                 up to {} are passed through exactly",
+                synthesis::MIN_POINTS,
+                synthesis::MAX_EXACT_POINTS
+            ))
+            .xalign(0.0)
+            .wrap(true)
+            .build(),
+    );
+    status.append(&synthesis_buttons);
+    status.append(&synthesis_result);
+    status.append(&heading("Motion synthesis"));
+    status.append(
+        &Label::builder()
+            .label(format!(
+                "Click {} or {} coupler positions in order in \"Place coupler positions\" mode, \
+                 dragging out their direction. Drag the pins, and for two positions the \
+                 ground pivots along their bisectors, to choose the four-bar",
+                synthesis::MIN_POSITIONS,
+                synthesis::MAX_POSITIONS
+            ))
+            .xalign(0.0)
+            .wrap(true)
+            .build(),
+    );
+    status.append(&motion_buttons);
+    status.append(&motion_result);
+
+    let editor = PropertyEditor::new({
+        let state = state.clone();
+        let branch_dropdown = branch.clone();
+        let area = drawing_area.clone();
+        move |edit| {
+            let current = {
+                let mut state = state.borrow_mut();
+                if state.animation.is_some() || state.linkage.is_some() {
+                    return;
+                }
+
+                let before = state.pose;
+                let edited = match edit {
+                    Edit::Joint(joint, p) => {
+                        let mut pose = before;
+                        *pose.joint_mut(joint) = p;
+                        Ok(pose)
+                    }
+                    Edit::Length(link, length) => before.with_length(link, length),
+                };
+                edited.map(|after| {
+                    state.pose = after;
+                    state.history.push(Command::Edit { before, after });
+                    FourBar::from_pose(&after).branch
+                })
+            };
+            let current = match current {
+                Ok(current) => current,
+                Err(err) => {
+                    // the editor shows the old length again on the next frame
+                    if let Some(window) = area
+                        .root()
+                        .and_then(|root| root.downcast::<ApplicationWindow>().ok())
+                    {
+                        app::file::show_error(&window, "Could not resize the link", &err);
+                    }
+                    return;
+                }
+            };
+            branch_dropdown.set_selected(branch_index(current));
+            area.queue_draw();
+        }
+    });
+
+    let tracers = TracerPanel::new({
+        let state = state.clone();
+        let area = drawing_area.clone();
+        move |edit| {
+            {
+                let mut state = state.borrow_mut();
+                let before = state.tracers.clone();
+                match edit {
+                    TracerEdit::Add => {
+                        let count = state.tracers.len();
+                        let tracer = new_tracer(&state.pose, state.linkage.as_ref(), count);
+                        state.tracers.extend(tracer);
+                    }
+                    TracerEdit::Remove(index) => {
+                        if index < state.tracers.len() {
+                            state.tracers.remove(index);
+                        }
+                    }
+                    TracerEdit::Change(index, tracer) => {
+                        if let Some(slot) = state.tracers.get_mut(index) {
+                            *slot = tracer;
+                        }
+                    }
+                }
+                let after = state.tracers.clone();
+                state.history.push(Command::EditTracers { before, after });
+            }
+            area.queue_draw();
         }
     });
 
-    let grid = Grid::builder().row_spacing(10).build();
+    let dimensions = gtk::Box::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .build();
+    dimensions.append(&heading("Dimensions"));
+    dimensions.append(editor.widget());
+    dimensions.append(&heading("Tracers"));
+    dimensions.append(tracers.widget());
+
+    let grid = Grid::builder().row_spacing(10).column_spacing(10).build();
     grid.attach(&drawing_area, 0, 0, 1, 1);
-    grid.attach(&button, 0, 1, 1, 1);
+    grid.attach(&controls, 0, 1, 1, 1);
+    grid.attach(&playback, 0, 2, 1, 1);
+    grid.attach(&timeline, 0, 3, 1, 1);
+    grid.attach(&plots, 1, 0, 1, 1);
+    grid.attach(&dimensions, 2, 0, 1, 1);
+    grid.attach(&status, 3, 0, 1, 1);
 
     // Create a window
     let window = ApplicationWindow::builder()
         .application(app)
         .title("My GTK App")
         .child(&grid)
+        .show_menubar(true)
         .build();
 
-    window.add_tick_callback(|window, _| {
-        if ANIMATE.lock().unwrap().is_some() {
-            let mut joints = FOUR_BAR.lock().unwrap();
+    // Puts a loaded design into the model and all widgets showing it.
+    let apply_design = {
+        let button = button.clone();
+        let branch = branch.clone();
+        let show_both_branches = show_both_branches.clone();
+        let min_transmission_angle = min_transmission_angle.clone();
+        let show_velocities = show_velocities.clone();
+        let show_accelerations = show_accelerations.clone();
+        let fit = fit.clone();
+        let state = state.clone();
+        move |design: &Design| {
+            button.set_active(false);
+            {
+                let mut state = state.borrow_mut();
+                state.pose = design.pose();
+                state.linkage = design.linkage.clone();
+                state.tracers = design.tracers.clone();
+                state.display = design.display;
+            }
+
+            branch.set_selected(branch_index(design.branch));
+            show_both_branches.set_active(design.display.show_both_branches);
+            min_transmission_angle.set_value(design.display.min_transmission_angle);
+            show_velocities.set_active(design.display.show_velocities);
+            show_accelerations.set_active(design.display.show_accelerations);
+            fit.emit_clicked();
+        }
+    };
 
-            let crank_length = length(joints[1], joints[2]);
-            let theta = angle(joints[0], joints[1]);
-            let (dx, dy) = (joints[4].0 - joints[1].0, joints[4].1 - joints[1].1);
+    // Shows the state before (when undoing) or after (when redoing) a command.
+    let restore = {
+        let button = button.clone();
+        let branch = branch.clone();
+        let apply_design = apply_design.clone();
+        let state = state.clone();
+        move |command: Command, undoing: bool| {
+            // the command may have been made on the pose the animation
+            // started from
+            button.set_active(false);
+            match command {
+                Command::Edit { before, after } => {
+                    let pose = if undoing { before } else { after };
+                    state.borrow_mut().pose = pose;
+                    branch.set_selected(branch_index(FourBar::from_pose(&pose).branch));
+                }
+                Command::EditLinkage { before, after } => {
+                    state.borrow_mut().linkage = Some(if undoing { before } else { after });
+                }
+                Command::EditTracers { before, after } => {
+                    state.borrow_mut().tracers = if undoing { before } else { after };
+                }
+                Command::Load { before, after } => {
+                    apply_design(if undoing { &before } else { &after })
+                }
+            }
+        }
+    };
 
-            let a = 0.02 + angle(joints[1], joints[2]);
-            let p1 = (
-                joints[2].0 + crank_length * a.cos(),
-                joints[2].1 - crank_length * a.sin(),
-            );
-            let p3 = get_rocker_pos(p1, *joints);
-            let psi = angle(p3, p1);
-            let p4 = (
-                p1.0 + dx * (theta - psi).cos() - dy * (theta - psi).sin(),
-                p1.1 + dy * (theta - psi).cos() + dx * (theta - psi).sin(),
+    // Replaces the mechanism by a synthesised four-bar, undone in one step.
+    let load_four_bar = {
+        let state = state.clone();
+        let apply_design = apply_design.clone();
+        move |pose: Pose| {
+            let before = state.borrow().design();
+            // tracers on the bodies of a general linkage do not carry over
+            // to the four-bar
+            let tracers = match before.linkage {
+                Some(_) => Vec::new(),
+                None => before.tracers.clone(),
+            };
+            let after = Design {
+                tracers,
+                ..Design::new(pose, before.display)
+            };
+            apply_design(&after);
+            state.borrow_mut().history.push(Command::Load {
+                before: Box::new(before),
+                after: Box::new(after),
+            });
+        }
+    };
+
+    synthesise.connect_clicked({
+        let state = state.clone();
+        let load_four_bar = load_four_bar.clone();
+        move |_| {
+            let points = state.borrow().precision_points.clone();
+            match synthesis::path_generation(&points) {
+                Ok(found) => {
+                    load_four_bar(found.pose);
+                    synthesis_result.set_text(&format!(
+                        "Passes within {:.3} mm of all {} points",
+                        found.error,
+                        points.len()
+                    ));
+                }
+                Err(err) => synthesis_result.set_text(&err.to_string()),
+            }
+        }
+    });
+
+    synthesise_motion.connect_clicked({
+        let state = state.clone();
+        move |_| {
+            let found = {
+                let state = state.borrow();
+                synthesis::motion_generation(&state.coupler_positions, &state.motion_choices)
+            };
+            match found {
+                Ok(found) => {
+                    load_four_bar(found.pose);
+                    let count = found.crank_angles.len();
+                    motion_result.set_text(&if found.error < MOTION_TOLERANCE {
+                        format!("Takes up all {count} positions")
+                    } else {
+                        format!(
+                            "Has to change branch to take up all {count} positions, \
+                             missing by up to {:.1} mm; try other pins",
+                            found.error
+                        )
+                    });
+                }
+                Err(err) => motion_result.set_text(&err.to_string()),
+            }
+        }
+    });
+
+    let undo = gio::SimpleAction::new("undo", None);
+    undo.connect_activate({
+        let state = state.clone();
+        let restore = restore.clone();
+        move |_, _| {
+            let command = state.borrow_mut().history.undo();
+            if let Some(command) = command {
+                restore(command, true);
+            }
+        }
+    });
+    window.add_action(&undo);
+
+    let redo = gio::SimpleAction::new("redo", None);
+    redo.connect_activate({
+        let state = state.clone();
+        move |_, _| {
+            let command = state.borrow_mut().history.redo();
+            if let Some(command) = command {
+                restore(command, false);
+            }
+        }
+    });
+    window.add_action(&redo);
+
+    let template = gio::SimpleAction::new("template", Some(glib::VariantTy::UINT32));
+    template.connect_activate({
+        let state = state.clone();
+        let apply_design = apply_design.clone();
+        move |_, parameter| {
+            let Some(template) = parameter
+                .and_then(|parameter| parameter.get::<u32>())
+                .and_then(|index| Template::ALL.get(index as usize))
+            else {
+                return;
+            };
+            let design = template.design();
+            let before = state.borrow().design();
+            apply_design(&design);
+            let mut state = state.borrow_mut();
+            state.history.push(Command::Load {
+                before: Box::new(before),
+                after: Box::new(design),
+            });
+            state.current_file = None;
+        }
+    });
+    window.add_action(&template);
+
+    let open = gio::SimpleAction::new("open", None);
+    let weak_window = window.downgrade();
+    let open_state = state.clone();
+    open.connect_activate(move |_, _| {
+        let Some(window) = weak_window.upgrade() else {
+            return;
+        };
+        let parent = window.clone();
+        let apply_design = apply_design.clone();
+        let state = open_state.clone();
+        app::file::choose_file(
+            &window,
+            "Open Design",
+            FileChooserAction::Open,
+            &app::file::DESIGN,
+            move |path| match Design::load(&path) {
+                Ok(design) => {
+                    let before = state.borrow().design();
+                    apply_design(&design);
+                    let mut state = state.borrow_mut();
+                    state.history.push(Command::Load {
+                        before: Box::new(before),
+                        after: Box::new(design),
+                    });
+                    state.current_file = Some(path);
+                }
+                Err(err) => app::file::show_error(&parent, "Could not open design", &err),
+            },
+        );
+    });
+    window.add_action(&open);
+
+    let save_as = gio::SimpleAction::new("save-as", None);
+    let weak_window = window.downgrade();
+    let save_as_state = state.clone();
+    save_as.connect_activate(move |_, _| {
+        let Some(window) = weak_window.upgrade() else {
+            return;
+        };
+        let parent = window.clone();
+        let state = save_as_state.clone();
+        app::file::choose_file(
+            &window,
+            "Save Design",
+            FileChooserAction::Save,
+            &app::file::DESIGN,
+            move |path| save_design(&parent, &state, path),
+        );
+    });
+    window.add_action(&save_as);
+
+    let save = gio::SimpleAction::new("save", None);
+    let weak_window = window.downgrade();
+    let save_as_action = save_as.clone();
+    let save_state = state.clone();
+    save.connect_activate(move |_, _| {
+        let Some(window) = weak_window.upgrade() else {
+            return;
+        };
+        let path = save_state.borrow().current_file.clone();
+        match path {
+            Some(path) => save_design(&window, &save_state, path),
+            None => save_as_action.activate(None),
+        }
+    });
+    window.add_action(&save);
+
+    let export_csv = gio::SimpleAction::new("export-csv", None);
+    let weak_window = window.downgrade();
+    let export_state = state.clone();
+    export_csv.connect_activate(move |_, _| {
+        let Some(window) = weak_window.upgrade() else {
+            return;
+        };
+        let parent = window.clone();
+        let state = export_state.clone();
+        app::file::choose_file(
+            &window,
+            "Export Motion",
+            FileChooserAction::Save,
+            &app::file::CSV,
+            move |path| {
+                let (four_bar, linkage, tracers) = {
+                    let state = state.borrow();
+                    (
+                        FourBar::from_pose(&state.reference_pose()),
+                        state.reference_linkage(),
+                        state.tracers.clone(),
+                    )
+                };
+                let result = File::create(path).and_then(|file| {
+                    let out = BufWriter::new(file);
+                    match &linkage {
+                        Some(linkage) => export::write_linkage_csv(
+                            linkage,
+                            &tracers,
+                            COUPLER_CURVE_RESOLUTION,
+                            out,
+                        ),
+                        None => {
+                            export::write_csv(&four_bar, &tracers, COUPLER_CURVE_RESOLUTION, out)
+                        }
+                    }
+                });
+                if let Err(err) = result {
+                    app::file::show_error(&parent, "Could not export motion", &err);
+                }
+            },
+        );
+    });
+    window.add_action(&export_csv);
+
+    for (name, kind, format) in [
+        ("export-svg", &app::file::SVG, app::export::Format::Svg),
+        ("export-pdf", &app::file::PDF, app::export::Format::Pdf),
+    ] {
+        let action = gio::SimpleAction::new(name, None);
+        let weak_window = window.downgrade();
+        let export_state = state.clone();
+        action.connect_activate(move |_, _| {
+            let Some(window) = weak_window.upgrade() else {
+                return;
+            };
+            let parent = window.clone();
+            let state = export_state.clone();
+            app::file::choose_file(
+                &window,
+                "Export Drawing",
+                FileChooserAction::Save,
+                kind,
+                move |path| {
+                    let (design, crank) = {
+                        let state = state.borrow();
+                        (state.design(), state.crank_input)
+                    };
+                    let result = app::export::export_drawing(&path, format, &design, crank);
+                    if let Err(err) = result {
+                        app::file::show_error(&parent, "Could not export drawing", &err);
+                    }
+                },
             );
+        });
+        window.add_action(&action);
+    }
+
+    window.add_tick_callback({
+        let state = state.clone();
+        let drawing_area = drawing_area.clone();
+        let branch = branch.clone();
+        move |_, frame_clock| {
+            let (
+                pose,
+                linkage,
+                animating,
+                crank_angle,
+                min_angle,
+                crank,
+                tracer_list,
+                links,
+                range,
+            ) = {
+                let state = &mut *state.borrow_mut();
+                let now = frame_clock.frame_time();
+                let frame = state.animation.as_mut().map(|animation| {
+                    let elapsed = animation
+                        .last_frame
+                        .map_or(0.0, |last| (now - last) as f64 / 1e6)
+                        .min(MAX_FRAME_TIME);
+                    animation.last_frame = Some(now);
+                    (animation.crank_angle, animation.direction, elapsed)
+                });
+                if let Some((angle, direction, elapsed)) = frame {
+                    if !state.paused {
+                        let sign = if state.reverse { -1.0 } else { 1.0 };
+                        let omega = state.speed_rpm * TAU / 60.0 * direction * sign;
+                        // turn around at dead points instead of running into them
+                        if !state.turn_to(angle + omega * elapsed) {
+                            if let Some(animation) = state.animation.as_mut() {
+                                animation.direction = -direction;
+                            }
+                        }
+                    }
+                }
+
+                state.update_four_bar_paths();
+
+                undo.set_enabled(state.history.can_undo());
+                redo.set_enabled(state.history.can_redo());
+                (
+                    state.pose,
+                    state.linkage.as_ref().map(|linkage| {
+                        (
+                            linkage.bodies.len(),
+                            linkage.sliders.len(),
+                            linkage.mobility(),
+                        )
+                    }),
+                    state.animation.is_some(),
+                    state.crank_angle(),
+                    state.display.min_transmission_angle.to_radians(),
+                    state.crank_input,
+                    state.tracers.clone(),
+                    moving_links(state.linkage.as_ref()),
+                    state.four_bar_paths.transmission,
+                )
+            };
+
+            // the editor, branches, plots and analyses are of four-bars
+            let is_four_bar = linkage.is_none();
+            editor.update(&pose);
+            tracers.update(&tracer_list, links);
+            editor.widget().set_sensitive(!animating && is_four_bar);
+            branch.set_sensitive(is_four_bar);
+            step_back.set_sensitive(animating);
+            step_forward.set_sensitive(animating);
+            let degrees = crank_angle.rem_euclid(TAU).to_degrees();
+            if (scrubber.value() - degrees).abs() > 1e-9 {
+                scrubber.set_value(degrees);
+            }
+
+            if let Some((bodies, sliders, mobility)) = linkage {
+                mechanism_type.set_text(&format!(
+                    "{bodies} bodies, {sliders} sliders\n{mobility} degrees of freedom"
+                ));
+                transmission.set_text("only shown for four-bars");
+                transmission_warning.set_text("");
+                motion.set_text("only shown for four-bars");
+                drawing_area.queue_draw();
+                plots.queue_draw();
+                return glib::ControlFlow::Continue;
+            }
+
+            let four_bar = FourBar::from_pose(&pose);
+            let linkage_type = four_bar.linkage_type();
+            mechanism_type.set_text(&format!(
+                "{}\n{}",
+                linkage_type,
+                if linkage_type.is_grashof() {
+                    "Grashof"
+                } else {
+                    "non-Grashof"
+                }
+            ));
 
-            joints[0] = p3;
-            joints[1] = p1;
-            joints[4] = p4;
-        }
-        window
-            .child()
-            .unwrap()
-            .downcast_ref::<Grid>()
-            .unwrap()
-            .child_at(0, 0)
-            .unwrap()
-            .queue_draw();
-        glib::ControlFlow::Continue
+            match range {
+                Some(range) => {
+                    transmission.set_text(&format!(
+                        "{:.1}° now\n{:.1}° to {:.1}° over the cycle",
+                        pose.transmission_angle().to_degrees(),
+                        range.min.to_degrees(),
+                        range.max.to_degrees(),
+                    ));
+                    transmission_warning.set_markup(&if range.is_below(min_angle) {
+                        format!(
+                            "<span foreground=\"red\">Drops to {:.1}°, below {:.0}°</span>",
+                            range.worst().to_degrees(),
+                            min_angle.to_degrees(),
+                        )
+                    } else {
+                        String::new()
+                    });
+                }
+                None => {
+                    transmission.set_text("cannot assemble");
+                    transmission_warning.set_text("");
+                }
+            }
+
+            motion.set_text(&match pose.motion(crank.omega, crank.alpha) {
+                Some(m) => format_motion(&m),
+                None => "dead point: coupler and rocker are collinear".to_string(),
+            });
+            drawing_area.queue_draw();
+            plots.queue_draw();
+            glib::ControlFlow::Continue
+        }
     });
 
     // Present window
@@ -0,0 +1,215 @@
+//! Velocity and acceleration analysis of the four-bar linkage.
+//!
+//! Angular quantities follow the sign of [`Pose::crank_angle`], i.e. they
+//! are positive counter-clockwise.
+
+use crate::four_bar::Pose;
+use crate::geometry::Point;
+
+/// Angular velocity and acceleration of a link.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinkMotion {
+    /// Angular velocity in rad/s.
+    pub omega: f64,
+    /// Angular acceleration in rad/s².
+    pub alpha: f64,
+}
+
+/// Velocity and acceleration of a point, per second and per second squared.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PointMotion {
+    pub velocity: Point,
+    pub acceleration: Point,
+}
+
+/// Velocities and accelerations of all moving parts of a four-bar.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Motion {
+    pub crank: LinkMotion,
+    pub coupler: LinkMotion,
+    pub rocker: LinkMotion,
+    pub crank_pin: PointMotion,
+    pub rocker_pin: PointMotion,
+    pub coupler_point: PointMotion,
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// `r` turned by 90° counter-clockwise, so that `omega * perp(r)` is the
+/// velocity of the tip of `r` turning at `omega`.
+fn perp(r: Point) -> Point {
+    (-r.1, r.0)
+}
+
+/// Velocity and acceleration of the tip of `r`, relative to its base,
+/// when `r` turns with `link`.
+fn relative(r: Point, link: LinkMotion) -> PointMotion {
+    let t = perp(r);
+    PointMotion {
+        velocity: (link.omega * t.0, link.omega * t.1),
+        acceleration: (
+            link.alpha * t.0 - link.omega.powf(2.0) * r.0,
+            link.alpha * t.1 - link.omega.powf(2.0) * r.1,
+        ),
+    }
+}
+
+/// Solves `x * p - y * q = rhs` for `x` and `y`, if `p` and `q` are not
+/// parallel.
+fn solve(p: Point, q: Point, rhs: Point) -> Option<(f64, f64)> {
+    let det = q.0 * p.1 - p.0 * q.1;
+    let scale = (p.0.powf(2.0) + p.1.powf(2.0)).sqrt() * (q.0.powf(2.0) + q.1.powf(2.0)).sqrt();
+    if det.abs() <= 1e-9 * scale {
+        return None;
+    }
+    Some((
+        (q.0 * rhs.1 - q.1 * rhs.0) / det,
+        (p.0 * rhs.1 - p.1 * rhs.0) / det,
+    ))
+}
+
+impl Pose {
+    /// Analyses velocities and accelerations for the crank turning at
+    /// `crank_omega` (rad/s) and speeding up at `crank_alpha` (rad/s²).
+    ///
+    /// Returns `None` in dead points, where coupler and rocker are collinear
+    /// and the rocker's motion is not determined by the crank.
+    pub fn motion(&self, crank_omega: f64, crank_alpha: f64) -> Option<Motion> {
+        let crank = LinkMotion {
+            omega: crank_omega,
+            alpha: crank_alpha,
+        };
+        let r_crank = sub(self.crank_pin, self.crank_pivot);
+        let r_coupler = sub(self.rocker_pin, self.crank_pin);
+        let r_rocker = sub(self.rocker_pin, self.rocker_pivot);
+        let r_coupler_point = sub(self.coupler_point, self.crank_pin);
+
+        let crank_pin = relative(r_crank, crank);
+
+        // v_B + w3 x r_BC = w4 x r_DC
+        let (coupler_omega, rocker_omega) = solve(
+            perp(r_coupler),
+            perp(r_rocker),
+            (-crank_pin.velocity.0, -crank_pin.velocity.1),
+        )?;
+        // the same with the centripetal terms moved to the right hand side
+        let (coupler_alpha, rocker_alpha) = solve(
+            perp(r_coupler),
+            perp(r_rocker),
+            (
+                -crank_pin.acceleration.0 + coupler_omega.powf(2.0) * r_coupler.0
+                    - rocker_omega.powf(2.0) * r_rocker.0,
+                -crank_pin.acceleration.1 + coupler_omega.powf(2.0) * r_coupler.1
+                    - rocker_omega.powf(2.0) * r_rocker.1,
+            ),
+        )?;
+
+        let coupler = LinkMotion {
+            omega: coupler_omega,
+            alpha: coupler_alpha,
+        };
+        let rocker = LinkMotion {
+            omega: rocker_omega,
+            alpha: rocker_alpha,
+        };
+        let on_coupler = |r: Point| {
+            let rel = relative(r, coupler);
+            PointMotion {
+                velocity: (
+                    crank_pin.velocity.0 + rel.velocity.0,
+                    crank_pin.velocity.1 + rel.velocity.1,
+                ),
+                acceleration: (
+                    crank_pin.acceleration.0 + rel.acceleration.0,
+                    crank_pin.acceleration.1 + rel.acceleration.1,
+                ),
+            }
+        };
+
+        Some(Motion {
+            crank,
+            coupler,
+            rocker,
+            crank_pin,
+            rocker_pin: on_coupler(r_coupler),
+            coupler_point: on_coupler(r_coupler_point),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{PI, TAU};
+
+    use crate::four_bar::{FourBar, Pose};
+    use crate::templates;
+
+    /// Step in crank angle for the finite differences, in radians.
+    const H: f64 = 1e-4;
+
+    /// `b − a` for angles, without the jump at ±180°.
+    fn turned(a: f64, b: f64) -> f64 {
+        (b - a + PI).rem_euclid(TAU) - PI
+    }
+
+    #[test]
+    fn agrees_with_finite_differences() {
+        let four_bar = FourBar::from_pose(&templates::four_bar());
+        let (omega, alpha) = (2.0, 3.0);
+        for step in 0..12 {
+            let theta = TAU * step as f64 / 12.0;
+            let [before, pose, after] =
+                [theta - H, theta, theta + H].map(|a| four_bar.solve(a).unwrap());
+            let motion = pose.motion(omega, alpha).unwrap();
+
+            // d/dt = ω d/dθ and d²/dt² = ω² d²/dθ² + α d/dθ
+            let rate = |f: &dyn Fn(&Pose) -> f64| {
+                let first = (f(&after) - f(&before)) / (2.0 * H);
+                let second = (f(&after) - 2.0 * f(&pose) + f(&before)) / (H * H);
+                (omega * first, omega * omega * second + alpha * first)
+            };
+            let angle_rate = |angle: fn(&Pose) -> f64| {
+                let first = turned(angle(&before), angle(&after)) / (2.0 * H);
+                let second = (turned(angle(&pose), angle(&after))
+                    - turned(angle(&before), angle(&pose)))
+                    / (H * H);
+                (omega * first, omega * omega * second + alpha * first)
+            };
+            let close = |(v, a): (f64, f64), (expected_v, expected_a): (f64, f64)| {
+                assert!(
+                    (v - expected_v).abs() < 1e-5 * (1.0 + v.abs()),
+                    "{v} != {expected_v}"
+                );
+                assert!(
+                    (a - expected_a).abs() < 1e-3 * (1.0 + a.abs()),
+                    "{a} != {expected_a}"
+                );
+            };
+
+            close(
+                (motion.coupler.omega, motion.coupler.alpha),
+                angle_rate(|p| p.coupler_angle()),
+            );
+            close(
+                (motion.rocker.omega, motion.rocker.alpha),
+                angle_rate(|p| p.rocker_angle()),
+            );
+            for (point, get) in [
+                (motion.crank_pin, (|p| p.crank_pin) as fn(&Pose) -> _),
+                (motion.rocker_pin, |p| p.rocker_pin),
+                (motion.coupler_point, |p| p.coupler_point),
+            ] {
+                close(
+                    (point.velocity.0, point.acceleration.0),
+                    rate(&|p| get(p).0),
+                );
+                close(
+                    (point.velocity.1, point.acceleration.1),
+                    rate(&|p| get(p).1),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1032 @@
+//! Synthesis of four-bars from what they should do.
+//!
+//! Path generation finds a four-bar whose coupler point passes through
+//! given precision points, in order, as the crank turns one way.
+//!
+//! Up to [`MAX_EXACT_POINTS`] points are matched exactly by the loop-closure
+//! equations of the two dyads, crank with coupler and rocker with coupler,
+//! in the standard form of Erdman and Sandor:
+//!
+//! ```text
+//! W (e^iβj − 1) + Z (e^iαj − 1) = δj
+//! ```
+//!
+//! where `δj` leads from the first precision point to the `j`-th, and `βj`
+//! and `αj` are the rotations of the crank `W` and of the coupler `Z`
+//! between them. The rotations that the points do not fix are free
+//! choices; a range of them is tried and the four-bar that runs through the
+//! points best is kept. Further points are fitted in the least-squares
+//! sense, starting from exact fits through some of them.
+//!
+//! Motion generation finds a four-bar whose coupler takes up given
+//! positions, each a point and an angle, by the classical construction: the
+//! ground pivot of a dyad is the centre of the circle through the positions
+//! of its moving pivot, on the perpendicular bisector of two positions or
+//! where the bisectors of three meet. Every such bisector passes through the
+//! pole of the two coupler positions, the fixed point of the rotation
+//! taking one to the other, so for three positions the bisectors run
+//! through the corners of the pole triangle. Where the moving pivots sit on
+//! the coupler, and for two positions where the ground pivots sit on their
+//! bisectors, are free choices left to the caller.
+
+use std::f64::consts::{PI, TAU};
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+use crate::four_bar::{FourBar, Pose};
+use crate::geometry::{angle, length, Point};
+use crate::grashof::LinkageType;
+use crate::linkage::least_squares_step;
+
+/// Fewest precision points a path can be synthesised for.
+pub const MIN_POINTS: usize = 3;
+/// Most precision points a four-bar can be made to pass through exactly.
+pub const MAX_EXACT_POINTS: usize = 5;
+/// Fewest coupler positions a motion can be synthesised for.
+pub const MIN_POSITIONS: usize = 2;
+/// Most coupler positions a motion can be synthesised for by construction.
+pub const MAX_POSITIONS: usize = 3;
+
+/// Crank rotations from the first to the last precision point tried as
+/// free choices, in degrees. The points in between are spaced evenly.
+const CRANK_SPANS: [f64; 10] = [
+    90.0, 135.0, 180.0, 225.0, 270.0, -90.0, -135.0, -180.0, -225.0, -270.0,
+];
+/// Coupler rotations from the first to the last precision point tried as
+/// free choices or first guesses, in degrees.
+const COUPLER_SPANS: [f64; 6] = [30.0, 60.0, 90.0, -30.0, -60.0, -90.0];
+/// Rocker rotations from the first to the last precision point tried as
+/// free choices or first guesses, in degrees.
+const ROCKER_SPANS: [f64; 6] = [20.0, 40.0, 60.0, -20.0, -40.0, -60.0];
+/// Largest crank step, in radians, when checking that the linkage runs
+/// from one precision point to the next without a dead point.
+const RUN_STEP: f64 = PI / 180.0;
+/// Crank positions sampled per revolution to rate a four-bar and to find
+/// where it comes closest to each point before a least-squares fit.
+const SAMPLES: usize = 360;
+/// Exact fits through some of the points that are refined to fit all of
+/// them.
+const LEAST_SQUARES_STARTS: usize = 5;
+/// Newton and Gauss–Newton iterations before giving up.
+const MAX_ITERATIONS: usize = 100;
+/// Times a step is halved when it does not reduce the error.
+const MAX_STEP_HALVINGS: usize = 20;
+/// Distance, relative to the spread of the points, within which a point
+/// counts as reached.
+const TOLERANCE: f64 = 1e-8;
+
+/// A four-bar found by [`path_generation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSynthesis {
+    /// The four-bar with its coupler point on the first precision point.
+    pub pose: Pose,
+    /// Crank angle at which the coupler point comes to each precision
+    /// point.
+    pub crank_angles: Vec<f64>,
+    /// Largest distance between a precision point and the coupler point at
+    /// its crank angle: zero up to rounding for an exact fit.
+    pub error: f64,
+}
+
+/// Returned when no four-bar could be synthesised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SynthesisError {
+    /// Fewer than [`MIN_POINTS`] precision points were given.
+    TooFewPoints(usize),
+    /// None of the free choices gave a four-bar that runs through the
+    /// points.
+    NoSolution,
+    /// Not between [`MIN_POSITIONS`] and [`MAX_POSITIONS`] coupler
+    /// positions were given.
+    PositionCount(usize),
+    /// The positions of the moving pivot of the crank (0) or rocker (1)
+    /// fix no ground pivot: they coincide, because the pivot was chosen on
+    /// a pole, or three of them lie on a line.
+    NoGroundPivot(usize),
+}
+
+impl fmt::Display for SynthesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SynthesisError::TooFewPoints(count) => write!(
+                f,
+                "need at least {MIN_POINTS} precision points, but {count} were given"
+            ),
+            SynthesisError::NoSolution => {
+                write!(f, "no four-bar found that runs through the points")
+            }
+            SynthesisError::PositionCount(count) => write!(
+                f,
+                "need {MIN_POSITIONS} or {MAX_POSITIONS} coupler positions, but {count} were given"
+            ),
+            SynthesisError::NoGroundPivot(dyad) => write!(
+                f,
+                "the positions of the {} pin fix no ground pivot",
+                if *dyad == 0 { "crank" } else { "rocker" }
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SynthesisError {}
+
+/// A point of the plane as a complex number, for the dyad equations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn from_point(p: Point) -> Self {
+        Complex { re: p.0, im: p.1 }
+    }
+
+    /// The unit vector at `angle` radians.
+    fn unit(angle: f64) -> Self {
+        Complex {
+            re: angle.cos(),
+            im: angle.sin(),
+        }
+    }
+
+    /// Turned by 90° counter-clockwise, that is multiplied by `i`.
+    fn perp(self) -> Self {
+        Complex {
+            re: -self.im,
+            im: self.re,
+        }
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn checked_div(self, other: Complex) -> Option<Self> {
+        let d = other.norm_sqr();
+        (d > 0.0).then(|| Complex {
+            re: (self.re * other.re + self.im * other.im) / d,
+            im: (self.im * other.re - self.re * other.im) / d,
+        })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// `e^(i angle) − 1`, the change of a unit vector turned by `angle`.
+fn turn(angle: f64) -> Complex {
+    Complex::unit(angle) - Complex { re: 1.0, im: 0.0 }
+}
+
+/// Solves the standard-form equations `X (e^iφj − 1) + Y (e^iψj − 1) = δj`
+/// of a dyad for the vectors `X` and `Y`, given the rotations `phi` of `X`.
+///
+/// With two equations the rotations `psi` of `Y` are free choices and kept.
+/// With more, only as many of them stay fixed, from the first, as leave
+/// the equations as many as the unknowns; the others are solved for,
+/// starting from the values given.
+fn solve_dyad(
+    deltas: &[Complex],
+    phi: &[f64],
+    psi: &mut [f64],
+    tolerance: f64,
+) -> Option<(Complex, Complex)> {
+    let fixed = (MAX_EXACT_POINTS - 1).saturating_sub(deltas.len());
+    let residuals = |x: Complex, y: Complex, psi: &[f64]| -> Vec<f64> {
+        deltas
+            .iter()
+            .zip(phi.iter().zip(psi))
+            .flat_map(|(&delta, (&phi, &psi))| {
+                let r = x * turn(phi) + y * turn(psi) - delta;
+                [r.re, r.im]
+            })
+            .collect()
+    };
+    let largest = |r: &[f64]| r.iter().fold(0.0, |max: f64, r| max.max(r.abs()));
+    let squared = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+
+    // the first two equations alone are linear in X and Y
+    let (a, b) = ([turn(phi[0]), turn(phi[1])], [turn(psi[0]), turn(psi[1])]);
+    let det = a[0] * b[1] - a[1] * b[0];
+    let mut x = (deltas[0] * b[1] - deltas[1] * b[0]).checked_div(det)?;
+    let mut y = (a[0] * deltas[1] - a[1] * deltas[0]).checked_div(det)?;
+
+    let mut r = residuals(x, y, psi);
+    for _ in 0..MAX_ITERATIONS {
+        if largest(&r) <= tolerance {
+            return Some((x, y));
+        }
+        let jacobian: Vec<Vec<f64>> = (0..deltas.len())
+            .flat_map(|j| {
+                let (a, b) = (turn(phi[j]), turn(psi[j]));
+                let mut columns = [vec![a.re, -a.im, b.re, -b.im], vec![a.im, a.re, b.im, b.re]];
+                for k in fixed..psi.len() {
+                    let dpsi = if k == j {
+                        (y * Complex::unit(psi[j])).perp()
+                    } else {
+                        Complex { re: 0.0, im: 0.0 }
+                    };
+                    columns[0].push(dpsi.re);
+                    columns[1].push(dpsi.im);
+                }
+                columns
+            })
+            .collect();
+        let step = least_squares_step(&jacobian, &r)?;
+
+        let mut scale = 1.0;
+        let mut improved = false;
+        for _ in 0..MAX_STEP_HALVINGS {
+            let tried_x = x + Complex {
+                re: scale * step[0],
+                im: scale * step[1],
+            };
+            let tried_y = y + Complex {
+                re: scale * step[2],
+                im: scale * step[3],
+            };
+            let mut tried_psi = psi.to_vec();
+            for (k, change) in (fixed..psi.len()).zip(&step[4..]) {
+                tried_psi[k] += scale * change;
+            }
+            let tried = residuals(tried_x, tried_y, &tried_psi);
+            if squared(&tried) < squared(&r) {
+                (x, y, r) = (tried_x, tried_y, tried);
+                psi.copy_from_slice(&tried_psi);
+                improved = true;
+                break;
+            }
+            scale /= 2.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+    (largest(&r) <= tolerance).then_some((x, y))
+}
+
+/// Distance from the centre of `points` to the farthest of them, the scale
+/// of all tolerances.
+fn spread(points: &[Point]) -> f64 {
+    let n = points.len() as f64;
+    let centre = points
+        .iter()
+        .fold((0.0, 0.0), |c, p| (c.0 + p.0 / n, c.1 + p.1 / n));
+    points
+        .iter()
+        .map(|&p| length(p, centre))
+        .fold(0.0, f64::max)
+}
+
+/// A four-bar through the points, with how well it suits as a mechanism.
+#[derive(Debug, Clone)]
+struct Candidate {
+    synthesis: PathSynthesis,
+    /// Lower is better: the ratio of longest to shortest link over the sine
+    /// of the worst transmission angle, times how many times the longest
+    /// link outreaches the points, and doubled if the crank cannot turn
+    /// fully.
+    cost: f64,
+}
+
+/// Checks that `four_bar` runs from the first crank angle to each of the
+/// others in turn without a dead point, and rates it.
+fn rate(four_bar: &FourBar, points: &[Point], crank_angles: Vec<f64>) -> Option<Candidate> {
+    let first = crank_angles[0];
+    let mut error: f64 = 0.0;
+    let mut previous = first;
+    for (&p, &angle) in points.iter().zip(&crank_angles) {
+        let steps = ((angle - previous).abs() / RUN_STEP).ceil() as usize;
+        for k in 1..steps {
+            let between = previous + (angle - previous) * k as f64 / steps as f64;
+            four_bar.solve(between).ok()?;
+        }
+        error = error.max(length(four_bar.solve(angle).ok()?.coupler_point, p));
+        previous = angle;
+    }
+
+    let lengths = [
+        length(four_bar.crank_pivot, four_bar.rocker_pivot),
+        four_bar.crank_length,
+        four_bar.coupler_length,
+        four_bar.rocker_length,
+    ];
+    let shortest = lengths.iter().copied().fold(f64::INFINITY, f64::min);
+    let longest = lengths.iter().copied().fold(0.0, f64::max);
+    if shortest <= TOLERANCE * longest {
+        return None;
+    }
+    let worst = four_bar.transmission_angles(first, SAMPLES)?.worst();
+    let turns_fully = matches!(
+        four_bar.linkage_type(),
+        LinkageType::CrankRocker | LinkageType::DoubleCrank
+    );
+    let cost = longest / shortest / worst.sin().max(1e-3)
+        * (longest / spread(points)).max(1.0)
+        * if turns_fully { 1.0 } else { 2.0 };
+
+    Some(Candidate {
+        synthesis: PathSynthesis {
+            pose: four_bar.solve(first).ok()?,
+            crank_angles,
+            error,
+        },
+        cost,
+    })
+}
+
+/// All four-bars through `points`, of which there are at most
+/// [`MAX_EXACT_POINTS`], found by trying the free choices.
+fn exact_candidates(points: &[Point]) -> Vec<Candidate> {
+    let n = points.len();
+    let tolerance = TOLERANCE * spread(points);
+    let p1 = Complex::from_point(points[0]);
+    let deltas: Vec<Complex> = points[1..]
+        .iter()
+        .map(|&p| Complex::from_point(p) - p1)
+        .collect();
+    // rotations from the first to the last point, evenly spaced or easing
+    // off towards the end like those of a rocker
+    let spaced = |span: f64, easing: bool| -> Vec<f64> {
+        (1..n)
+            .map(|j| {
+                let t = j as f64 / (n - 1) as f64;
+                span.to_radians() * if easing { (t * PI / 2.0).sin() } else { t }
+            })
+            .collect()
+    };
+
+    let mut candidates = Vec::new();
+    for crank_span in CRANK_SPANS {
+        let beta = spaced(crank_span, false);
+        for coupler_span in COUPLER_SPANS {
+            let mut alpha = spaced(coupler_span, false);
+            let Some((w, z)) = solve_dyad(&deltas, &beta, &mut alpha, tolerance) else {
+                continue;
+            };
+            for rocker_span in ROCKER_SPANS {
+                let mut gamma = spaced(rocker_span, true);
+                let Some((s, u)) = solve_dyad(&deltas, &alpha, &mut gamma, tolerance) else {
+                    continue;
+                };
+                let point = |c: Complex| (c.re, c.im);
+                let pose = Pose {
+                    crank_pivot: point(p1 - z - w),
+                    crank_pin: point(p1 - z),
+                    rocker_pin: point(p1 - s),
+                    rocker_pivot: point(p1 - s - u),
+                    coupler_point: points[0],
+                };
+                let first = pose.crank_angle();
+                let crank_angles = std::iter::once(first)
+                    .chain(beta.iter().map(|beta| first + beta))
+                    .collect();
+                let four_bar = FourBar::from_pose(&pose);
+                if let Some(candidate) = rate(&four_bar, points, crank_angles) {
+                    if candidate.synthesis.error <= tolerance * 1e3 {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+    candidates
+}
+
+/// The dimensions of a four-bar and the crank angle for every point, as
+/// one vector for the least-squares fit.
+fn parameters(four_bar: &FourBar, crank_angles: &[f64]) -> Vec<f64> {
+    let mut x = vec![
+        four_bar.crank_pivot.0,
+        four_bar.crank_pivot.1,
+        four_bar.rocker_pivot.0,
+        four_bar.rocker_pivot.1,
+        four_bar.crank_length,
+        four_bar.coupler_length,
+        four_bar.rocker_length,
+        four_bar.coupler_point.along,
+        four_bar.coupler_point.across,
+    ];
+    x.extend_from_slice(crank_angles);
+    x
+}
+
+fn four_bar_from(x: &[f64], template: &FourBar) -> FourBar {
+    let mut four_bar = *template;
+    four_bar.crank_pivot = (x[0], x[1]);
+    four_bar.rocker_pivot = (x[2], x[3]);
+    four_bar.crank_length = x[4];
+    four_bar.coupler_length = x[5];
+    four_bar.rocker_length = x[6];
+    four_bar.coupler_point.along = x[7];
+    four_bar.coupler_point.across = x[8];
+    four_bar
+}
+
+/// How far the coupler point misses each point, or `None` if the four-bar
+/// cannot be assembled at one of the crank angles.
+fn misses(x: &[f64], template: &FourBar, points: &[Point]) -> Option<Vec<f64>> {
+    let four_bar = four_bar_from(x, template);
+    let mut r = Vec::with_capacity(2 * points.len());
+    for (&p, &angle) in points.iter().zip(&x[9..]) {
+        let reached = four_bar.solve(angle).ok()?.coupler_point;
+        r.extend([reached.0 - p.0, reached.1 - p.1]);
+    }
+    Some(r)
+}
+
+/// Moves `four_bar` and the crank angle of every point by Gauss–Newton
+/// steps until the coupler point comes as close to all `points` as it can.
+///
+/// The crank turns in `direction`, 1 for counter-clockwise or −1 for
+/// clockwise, from each point to the next; fits that break this are
+/// refused.
+fn least_squares(four_bar: &FourBar, points: &[Point], direction: f64) -> Option<Candidate> {
+    // start where the coupler curve comes closest to each point, counting
+    // on in `direction` from one point to the next
+    let curve: Vec<(f64, Point)> = four_bar
+        .revolution(0.0, SAMPLES)
+        .filter_map(|(angle, pose)| Some((angle, pose.ok()?.coupler_point)))
+        .collect();
+    let mut crank_angles: Vec<f64> = points
+        .iter()
+        .map(|&p| {
+            curve
+                .iter()
+                .min_by(|a, b| length(a.1, p).total_cmp(&length(b.1, p)))
+                .map(|&(angle, _)| angle)
+        })
+        .collect::<Option<_>>()?;
+    for j in 1..crank_angles.len() {
+        while direction * (crank_angles[j] - crank_angles[j - 1]) <= 0.0 {
+            crank_angles[j] += direction * TAU;
+        }
+    }
+    if !turns_one_way(&crank_angles) {
+        return None;
+    }
+
+    let size = spread(points);
+    let mut x = parameters(four_bar, &crank_angles);
+    let mut r = misses(&x, four_bar, points)?;
+    let squared = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+    for _ in 0..MAX_ITERATIONS {
+        // forward differences, with steps in millimetres for the
+        // dimensions and in radians for the crank angles
+        let columns: Option<Vec<Vec<f64>>> = (0..x.len())
+            .map(|k| {
+                let h = if k < 9 { 1e-7 * size } else { 1e-7 };
+                let mut moved = x.clone();
+                moved[k] += h;
+                let moved_r = misses(&moved, four_bar, points)?;
+                Some(moved_r.iter().zip(&r).map(|(a, b)| (a - b) / h).collect())
+            })
+            .collect();
+        let Some(columns) = columns else {
+            break;
+        };
+        let jacobian: Vec<Vec<f64>> = (0..r.len())
+            .map(|i| columns.iter().map(|column| column[i]).collect())
+            .collect();
+        let Some(step) = least_squares_step(&jacobian, &r) else {
+            break;
+        };
+
+        let mut scale = 1.0;
+        let mut improved = false;
+        for _ in 0..MAX_STEP_HALVINGS {
+            let tried: Vec<f64> = x.iter().zip(&step).map(|(x, s)| x + scale * s).collect();
+            if let Some(tried_r) = misses(&tried, four_bar, points) {
+                if squared(&tried_r) < squared(&r) * (1.0 - 1e-12) {
+                    (x, r) = (tried, tried_r);
+                    improved = true;
+                    break;
+                }
+            }
+            scale /= 2.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    // the fit is free to reorder the points along the curve, which is no
+    // longer a path through them in order
+    if !turns_one_way(&x[9..]) {
+        return None;
+    }
+    let fitted = four_bar_from(&x, four_bar);
+    let mut candidate = rate(&fitted, points, x[9..].to_vec())?;
+    candidate.cost = candidate.synthesis.error;
+    Some(candidate)
+}
+
+/// Whether `crank_angles` run one way, all increasing or all decreasing,
+/// within less than one revolution.
+fn turns_one_way(crank_angles: &[f64]) -> bool {
+    let (Some(first), Some(last)) = (crank_angles.first(), crank_angles.last()) else {
+        return true;
+    };
+    let direction = (last - first).signum();
+    (last - first).abs() < TAU
+        && crank_angles
+            .windows(2)
+            .all(|pair| direction * (pair[1] - pair[0]) > 0.0)
+}
+
+/// Every `k`-th of `points`, so that `count` of them spread from the first
+/// to the last.
+fn spread_subset(points: &[Point], count: usize) -> Vec<Point> {
+    (0..count)
+        .map(|k| points[k * (points.len() - 1) / (count - 1)])
+        .collect()
+}
+
+/// Finds a four-bar whose coupler point passes through `points`, in order,
+/// as the crank turns one way.
+///
+/// Up to [`MAX_EXACT_POINTS`] points are passed through exactly if one of
+/// the free choices allows it, preferring four-bars whose crank turns
+/// fully, with good transmission angles and links of similar length. For
+/// more points, or if no exact fit runs through them, the error is
+/// minimised in the least-squares sense instead, see
+/// [`PathSynthesis::error`].
+pub fn path_generation(points: &[Point]) -> Result<PathSynthesis, SynthesisError> {
+    if points.len() < MIN_POINTS {
+        return Err(SynthesisError::TooFewPoints(points.len()));
+    }
+    if points.len() <= MAX_EXACT_POINTS {
+        if let Some(best) = exact_candidates(points).into_iter().next() {
+            return Ok(best.synthesis);
+        }
+    }
+
+    let mut starts = Vec::new();
+    for count in [MAX_EXACT_POINTS, MIN_POINTS] {
+        if starts.is_empty() && count <= points.len() {
+            starts = exact_candidates(&spread_subset(points, count));
+        }
+    }
+    starts
+        .iter()
+        .take(LEAST_SQUARES_STARTS)
+        .filter_map(|start| {
+            let angles = &start.synthesis.crank_angles;
+            let direction = (angles[angles.len() - 1] - angles[0]).signum();
+            least_squares(
+                &FourBar::from_pose(&start.synthesis.pose),
+                points,
+                direction,
+            )
+        })
+        .min_by(|a, b| a.cost.total_cmp(&b.cost))
+        .map(|best| best.synthesis)
+        .ok_or(SynthesisError::NoSolution)
+}
+
+/// A position of the coupler for motion generation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CouplerPosition {
+    /// Where the coupler point is.
+    pub point: Point,
+    /// Direction of the coupler, counter-clockwise from the x-axis, in
+    /// radians.
+    pub angle: f64,
+}
+
+impl CouplerPosition {
+    /// Where a point of the coupler is in this position, given by its
+    /// distance `along` the coupler's direction from the coupler point and
+    /// `across` it, positive to the left.
+    pub fn place(&self, (along, across): Point) -> Point {
+        let (sin, cos) = self.angle.sin_cos();
+        (
+            self.point.0 + along * cos - across * sin,
+            self.point.1 + along * sin + across * cos,
+        )
+    }
+
+    /// The inverse of [`CouplerPosition::place`]: how far `p` is along and
+    /// across the coupler in this position.
+    pub fn local(&self, p: Point) -> Point {
+        let (sin, cos) = self.angle.sin_cos();
+        let d = (p.0 - self.point.0, p.1 - self.point.1);
+        (d.0 * cos + d.1 * sin, d.1 * cos - d.0 * sin)
+    }
+}
+
+/// The pole of two coupler positions: the one point of the coupler that is
+/// in the same place in both, about which it turns from `from` to `to`.
+///
+/// Returns `None` if the coupler only translates, when the pole is at
+/// infinity.
+pub fn pole(from: &CouplerPosition, to: &CouplerPosition) -> Option<Point> {
+    // the pole p solves p = to.point + R(Δ) (p − from.point) for the
+    // rotation by Δ, that is (1 − e^iΔ) p = to.point − e^iΔ from.point
+    let rotation = Complex::unit(to.angle - from.angle);
+    let p = (Complex::from_point(to.point) - rotation * Complex::from_point(from.point))
+        .checked_div(Complex::unit(0.0) - rotation)?;
+    let p = (p.re, p.im);
+    let scale = length(from.point, to.point).max(1.0);
+    (length(p, from.point) < 1e9 * scale).then_some(p)
+}
+
+/// The poles of three coupler positions, `[P12, P13, P23]`, which span the
+/// pole triangle. Returns `None` if the coupler only translates between two
+/// of them, or if the poles lie on a line, as when the coupler only turns
+/// about one point.
+pub fn pole_triangle(positions: &[CouplerPosition; 3]) -> Option<[Point; 3]> {
+    let [first, second, third] = positions;
+    let poles = [
+        pole(first, second)?,
+        pole(first, third)?,
+        pole(second, third)?,
+    ];
+    let [p12, p13, p23] = poles;
+    let area = (p13.0 - p12.0) * (p23.1 - p12.1) - (p13.1 - p12.1) * (p23.0 - p12.0);
+    let size = length(p12, p13).max(length(p12, p23)).max(1.0);
+    (area.abs() > TOLERANCE * size * size).then_some(poles)
+}
+
+/// The perpendicular bisector of `p` and `q`, as their midpoint and a unit
+/// vector along it, to the left when looking from `p` to `q`.
+pub fn perpendicular_bisector(p: Point, q: Point) -> Option<(Point, Point)> {
+    let distance = length(p, q);
+    if distance <= TOLERANCE * p.0.abs().max(p.1.abs()).max(1.0) {
+        return None;
+    }
+    Some((
+        ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0),
+        ((p.1 - q.1) / distance, (q.0 - p.0) / distance),
+    ))
+}
+
+/// The choices motion generation leaves open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionChoices {
+    /// Where the crank pin and the rocker pin sit on the coupler, as
+    /// distances along and across it from the coupler point, see
+    /// [`CouplerPosition::place`].
+    pub moving_pivots: [Point; 2],
+    /// For two positions, how far along its perpendicular bisector each
+    /// ground pivot lies from the middle, see [`perpendicular_bisector`].
+    /// Three positions fix the ground pivots, so these are ignored.
+    pub bisector_offsets: [f64; 2],
+}
+
+impl Default for MotionChoices {
+    fn default() -> Self {
+        MotionChoices {
+            moving_pivots: [(-100.0, -60.0), (100.0, -60.0)],
+            bisector_offsets: [-150.0, -150.0],
+        }
+    }
+}
+
+/// How the ground pivot of one dyad was constructed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DyadConstruction {
+    /// The moving pivot in every coupler position.
+    pub moving_pivots: Vec<Point>,
+    /// Perpendicular bisectors of each position of the moving pivot and
+    /// the next, see [`perpendicular_bisector`].
+    pub bisectors: Vec<(Point, Point)>,
+    /// Where the bisectors meet, or the chosen point on the only one.
+    pub ground_pivot: Point,
+}
+
+/// Constructs the ground pivot for a moving pivot at `moving_pivot` on the
+/// coupler in `positions`, two or three of them, putting it `offset` along
+/// the bisector for two.
+pub fn dyad_construction(
+    positions: &[CouplerPosition],
+    moving_pivot: Point,
+    offset: f64,
+) -> Option<DyadConstruction> {
+    let moving_pivots: Vec<Point> = positions.iter().map(|p| p.place(moving_pivot)).collect();
+    let bisectors: Vec<(Point, Point)> = moving_pivots
+        .windows(2)
+        .map(|pair| perpendicular_bisector(pair[0], pair[1]))
+        .collect::<Option<_>>()?;
+    let ground_pivot = match bisectors.as_slice() {
+        [(middle, direction)] => (
+            middle.0 + offset * direction.0,
+            middle.1 + offset * direction.1,
+        ),
+        [(m1, d1), (m2, d2)] => {
+            // m1 + s d1 = m2 + t d2, solved for s by Cramer's rule
+            let det = d2.0 * d1.1 - d1.0 * d2.1;
+            let size = length(moving_pivots[0], moving_pivots[2]);
+            if det.abs() <= TOLERANCE {
+                return None;
+            }
+            let s = (d2.0 * (m2.1 - m1.1) - d2.1 * (m2.0 - m1.0)) / det;
+            if s.abs() > 1e6 * size {
+                return None;
+            }
+            (m1.0 + s * d1.0, m1.1 + s * d1.1)
+        }
+        _ => return None,
+    };
+    Some(DyadConstruction {
+        moving_pivots,
+        bisectors,
+        ground_pivot,
+    })
+}
+
+/// A four-bar found by [`motion_generation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionSynthesis {
+    /// The four-bar with its coupler in the first position.
+    pub pose: Pose,
+    /// The constructions of the crank and the rocker.
+    pub dyads: [DyadConstruction; 2],
+    /// Crank angle of every coupler position.
+    pub crank_angles: Vec<f64>,
+    /// Largest distance by which the coupler point or the rocker pin
+    /// misses a position when the four-bar is turned there without
+    /// changing branch: zero up to rounding unless the four-bar would have
+    /// to be taken apart between positions.
+    pub error: f64,
+}
+
+/// Finds a four-bar whose coupler takes up `positions`, two or three of
+/// them, with the moving pivots and, for two positions, the ground pivots
+/// where `choices` puts them.
+///
+/// The four-bar is returned however it runs between the positions; check
+/// [`MotionSynthesis::error`] for one that cannot reach them all on one
+/// branch.
+pub fn motion_generation(
+    positions: &[CouplerPosition],
+    choices: &MotionChoices,
+) -> Result<MotionSynthesis, SynthesisError> {
+    if !(MIN_POSITIONS..=MAX_POSITIONS).contains(&positions.len()) {
+        return Err(SynthesisError::PositionCount(positions.len()));
+    }
+    let construct = |dyad: usize| {
+        dyad_construction(
+            positions,
+            choices.moving_pivots[dyad],
+            choices.bisector_offsets[dyad],
+        )
+        .ok_or(SynthesisError::NoGroundPivot(dyad))
+    };
+    let dyads = [construct(0)?, construct(1)?];
+    let [crank, rocker] = &dyads;
+
+    let pose = Pose {
+        crank_pivot: crank.ground_pivot,
+        crank_pin: crank.moving_pivots[0],
+        rocker_pin: rocker.moving_pivots[0],
+        rocker_pivot: rocker.ground_pivot,
+        coupler_point: positions[0].point,
+    };
+    let four_bar = FourBar::from_pose(&pose);
+    let crank_angles: Vec<f64> = crank
+        .moving_pivots
+        .iter()
+        .map(|&pin| angle(pin, crank.ground_pivot))
+        .collect();
+    let mut error: f64 = 0.0;
+    for ((position, &rocker_pin), &crank_angle) in positions
+        .iter()
+        .zip(&rocker.moving_pivots)
+        .zip(&crank_angles)
+    {
+        let reached = four_bar
+            .solve(crank_angle)
+            .map_err(|_| SynthesisError::NoSolution)?;
+        error = error
+            .max(length(reached.coupler_point, position.point))
+            .max(length(reached.rocker_pin, rocker_pin));
+    }
+
+    Ok(MotionSynthesis {
+        pose,
+        dyads,
+        crank_angles,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Largest distance between a precision point and the coupler point at
+    /// its crank angle.
+    fn miss(synthesis: &PathSynthesis, points: &[Point]) -> f64 {
+        let four_bar = FourBar::from_pose(&synthesis.pose);
+        points
+            .iter()
+            .zip(&synthesis.crank_angles)
+            .map(|(&p, &angle)| length(four_bar.solve(angle).unwrap().coupler_point, p))
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn exact_fits_pass_through_every_point() {
+        let point_sets: [&[Point]; 3] = [
+            &[(0.0, 0.0), (100.0, 40.0), (180.0, 20.0)],
+            &[(0.0, 0.0), (80.0, 50.0), (160.0, 40.0), (220.0, -10.0)],
+            &[
+                (0.0, 0.0),
+                (60.0, 50.0),
+                (130.0, 60.0),
+                (190.0, 30.0),
+                (220.0, -20.0),
+            ],
+        ];
+        for points in point_sets {
+            let synthesis = path_generation(points).unwrap();
+            assert_eq!(synthesis.crank_angles.len(), points.len());
+            assert!(turns_one_way(&synthesis.crank_angles));
+            assert!(synthesis.error < 1e-3, "{}", synthesis.error);
+            assert!(miss(&synthesis, points) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn more_points_are_fitted_in_order() {
+        let points = [
+            (0.0, 0.0),
+            (50.0, 40.0),
+            (100.0, 60.0),
+            (150.0, 60.0),
+            (200.0, 40.0),
+            (250.0, 0.0),
+            (280.0, -40.0),
+        ];
+        let synthesis = path_generation(&points).unwrap();
+        assert_eq!(synthesis.crank_angles.len(), points.len());
+        assert!(turns_one_way(&synthesis.crank_angles));
+        assert!((synthesis.error - miss(&synthesis, &points)).abs() < 1e-9);
+        assert!(synthesis.error < 10.0, "{}", synthesis.error);
+    }
+
+    #[test]
+    fn crank_turning_back_is_refused() {
+        assert!(turns_one_way(&[0.0, 1.0, 2.0]));
+        assert!(turns_one_way(&[0.0, -1.0, -2.0]));
+        assert!(!turns_one_way(&[0.0, 2.0, 1.0]));
+        assert!(!turns_one_way(&[0.0, 3.0, 6.5]));
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(
+            path_generation(&[(0.0, 0.0), (1.0, 0.0)]).unwrap_err(),
+            SynthesisError::TooFewPoints(2)
+        );
+    }
+
+    /// Three coupler positions that turn and move the coupler unevenly.
+    fn three_positions() -> [CouplerPosition; 3] {
+        [
+            CouplerPosition {
+                point: (0.0, 0.0),
+                angle: 0.0,
+            },
+            CouplerPosition {
+                point: (100.0, 50.0),
+                angle: 0.6,
+            },
+            CouplerPosition {
+                point: (180.0, 20.0),
+                angle: 1.1,
+            },
+        ]
+    }
+
+    #[test]
+    fn poles_stay_in_place() {
+        let [first, second, third] = three_positions();
+        for (from, to) in [(&first, &second), (&first, &third), (&second, &third)] {
+            let p = pole(from, to).unwrap();
+            assert!(length(to.place(from.local(p)), p) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn translations_have_no_pole() {
+        let from = CouplerPosition {
+            point: (0.0, 0.0),
+            angle: 0.4,
+        };
+        let to = CouplerPosition {
+            point: (100.0, 50.0),
+            angle: 0.4,
+        };
+        assert_eq!(pole(&from, &to), None);
+        let [first, second, _] = three_positions();
+        let third = CouplerPosition {
+            point: (180.0, 20.0),
+            ..second
+        };
+        assert_eq!(pole_triangle(&[first, second, third]), None);
+    }
+
+    #[test]
+    fn collinear_pole_triangles_are_refused() {
+        // turning about one point puts every pole there
+        let about = (50.0, -30.0);
+        let turned = |angle: f64| CouplerPosition {
+            point: CouplerPosition {
+                point: about,
+                angle,
+            }
+            .place((-about.0, -about.1)),
+            angle,
+        };
+        let positions = [turned(0.0), turned(0.5), turned(1.2)];
+        assert!(pole_triangle(&positions).is_none());
+        assert!(pole_triangle(&three_positions()).is_some());
+    }
+
+    #[test]
+    fn ground_pivots_are_as_far_from_every_moving_pivot() {
+        let positions = three_positions();
+        for count in [2, 3] {
+            for offset in [-150.0, 0.0, 80.0] {
+                let dyad = dyad_construction(&positions[..count], (-100.0, -60.0), offset).unwrap();
+                let radius = length(dyad.ground_pivot, dyad.moving_pivots[0]);
+                for &pivot in &dyad.moving_pivots {
+                    assert!((length(dyad.ground_pivot, pivot) - radius).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moving_pivots_on_a_line_fix_no_ground_pivot() {
+        // translations along the coupler move every point of it on a line
+        let positions: Vec<CouplerPosition> = [0.0, 40.0, 100.0]
+            .into_iter()
+            .map(|along| CouplerPosition {
+                point: (along, 0.0),
+                angle: 0.0,
+            })
+            .collect();
+        assert_eq!(dyad_construction(&positions, (-100.0, -60.0), 0.0), None);
+        assert_eq!(
+            motion_generation(&positions, &MotionChoices::default()).unwrap_err(),
+            SynthesisError::NoGroundPivot(0)
+        );
+    }
+
+    #[test]
+    fn motion_generation_reaches_every_position() {
+        let positions = three_positions();
+        for count in [2, 3] {
+            let positions = &positions[..count];
+            let synthesis = motion_generation(positions, &MotionChoices::default()).unwrap();
+            assert_eq!(synthesis.crank_angles.len(), count);
+            assert!(synthesis.error < 1e-6, "{}", synthesis.error);
+            let four_bar = FourBar::from_pose(&synthesis.pose);
+            let first = four_bar.solve(synthesis.crank_angles[0]).unwrap();
+            for (position, &crank_angle) in positions.iter().zip(&synthesis.crank_angles) {
+                let reached = four_bar.solve(crank_angle).unwrap();
+                assert!(length(reached.coupler_point, position.point) < 1e-6);
+                // the coupler has turned as far as the position has
+                let turned = angle(reached.rocker_pin, reached.crank_pin)
+                    - angle(first.rocker_pin, first.crank_pin);
+                let difference = turned - (position.angle - positions[0].angle);
+                assert!(difference.sin().abs() < 1e-6 && difference.cos() > 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,447 @@
+//! Ready-made mechanisms to start a design from.
+//!
+//! All templates are laid out with the crank pivot at the origin and the
+//! crank at 60°, in millimetres. The six-bars are built onto the default
+//! [`four_bar`], by adding a dyad (two links pinned together) or, for the
+//! Stephenson III, by splitting its crank pin into a second loop.
+
+use serde::{Deserialize, Serialize};
+
+use crate::design::{Design, DisplaySettings};
+use crate::four_bar::{FourBar, Pose};
+use crate::geometry::Point;
+use crate::linkage::{Crank, Linkage, Slider};
+
+/// How far rails reach beyond the travel of their blocks, in millimetres.
+const RAIL_MARGIN: f64 = 30.0;
+/// Crank angle of every template, in radians.
+const CRANK_ANGLE: f64 = std::f64::consts::PI / 3.0;
+
+/// The mechanisms offered to start a new design from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Template {
+    FourBar,
+    SliderCrank,
+    InvertedSliderCrank,
+    ScotchYoke,
+    WattI,
+    WattII,
+    StephensonI,
+    StephensonII,
+    StephensonIII,
+}
+
+impl Template {
+    pub const ALL: [Template; 9] = [
+        Template::FourBar,
+        Template::SliderCrank,
+        Template::InvertedSliderCrank,
+        Template::ScotchYoke,
+        Template::WattI,
+        Template::WattII,
+        Template::StephensonI,
+        Template::StephensonII,
+        Template::StephensonIII,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Template::FourBar => "Four-bar linkage",
+            Template::SliderCrank => "Offset slider-crank",
+            Template::InvertedSliderCrank => "Inverted slider-crank",
+            Template::ScotchYoke => "Scotch yoke",
+            Template::WattI => "Watt I six-bar",
+            Template::WattII => "Watt II six-bar",
+            Template::StephensonI => "Stephenson I six-bar",
+            Template::StephensonII => "Stephenson II six-bar",
+            Template::StephensonIII => "Stephenson III six-bar",
+        }
+    }
+
+    pub fn is_six_bar(self) -> bool {
+        matches!(
+            self,
+            Template::WattI
+                | Template::WattII
+                | Template::StephensonI
+                | Template::StephensonII
+                | Template::StephensonIII
+        )
+    }
+
+    /// A new design with this mechanism and the default display settings.
+    pub fn design(self) -> Design {
+        let linkage = match self {
+            Template::FourBar => None,
+            Template::SliderCrank => Some(slider_crank(60.0, 200.0, 30.0, CRANK_ANGLE)),
+            Template::InvertedSliderCrank => Some(inverted_slider_crank(80.0, 150.0, CRANK_ANGLE)),
+            Template::ScotchYoke => Some(scotch_yoke(60.0, CRANK_ANGLE)),
+            Template::WattI => Some(watt_i(CRANK_ANGLE)),
+            Template::WattII => Some(watt_ii(CRANK_ANGLE)),
+            Template::StephensonI => Some(stephenson_i(CRANK_ANGLE)),
+            Template::StephensonII => Some(stephenson_ii(CRANK_ANGLE)),
+            Template::StephensonIII => Some(stephenson_iii(CRANK_ANGLE)),
+        };
+        Design {
+            linkage,
+            ..Design::new(four_bar(), DisplaySettings::default())
+        }
+    }
+}
+
+/// The crank-rocker every new window starts with.
+pub fn four_bar() -> Pose {
+    Pose {
+        crank_pivot: (0.0, 0.0),
+        crank_pin: (-50.0, 150.0),
+        rocker_pin: (200.0, 200.0),
+        rocker_pivot: (250.0, -50.0),
+        coupler_point: (90.0, 0.0),
+    }
+}
+
+/// The default [`four_bar`] turned to `crank_angle`.
+fn four_bar_at(crank_angle: f64) -> Pose {
+    // a crank-rocker assembles at every crank angle
+    FourBar::from_pose(&four_bar()).solve(crank_angle).unwrap()
+}
+
+/// The point a fraction `along` the way from `p` to `q` and `across`
+/// millimetres to the left of that line.
+fn offset(p: Point, q: Point, along: f64, across: f64) -> Point {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let scale = across / dx.hypot(dy);
+    (p.0 + along * dx - scale * dy, p.1 + along * dy + scale * dx)
+}
+
+/// End of a crank of length `crank` pivoting at the origin.
+fn crank_pin(crank: f64, crank_angle: f64) -> Point {
+    (crank * crank_angle.cos(), crank * crank_angle.sin())
+}
+
+/// A slider-crank whose block runs on a ground rail parallel to the x-axis,
+/// `offset` above the crank pivot.
+///
+/// The crank turns fully when `rod` is longer than `crank + |offset|`.
+pub fn slider_crank(crank: f64, rod: f64, offset: f64, crank_angle: f64) -> Linkage {
+    let [crank_pivot, pin, block, rail_start, rail_end] = [0, 1, 2, 3, 4];
+    let p = crank_pin(crank, crank_angle);
+    let rise = offset - p.1;
+    let near = ((rod - crank).powi(2) - offset.powi(2)).max(0.0).sqrt();
+    let far = ((rod + crank).powi(2) - offset.powi(2)).sqrt();
+
+    Linkage {
+        joints: vec![
+            (0.0, 0.0),
+            p,
+            (p.0 + (rod.powi(2) - rise.powi(2)).sqrt(), offset),
+            (near - RAIL_MARGIN, offset),
+            (far + RAIL_MARGIN, offset),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rail_start, rail_end],
+            vec![crank_pivot, pin],
+            vec![pin, block],
+            vec![block],
+        ],
+        sliders: vec![Slider {
+            guide: 0,
+            rail: (rail_start, rail_end),
+            block: 3,
+            joint: block,
+        }],
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin,
+        }],
+    }
+}
+
+/// An inverted slider-crank: a block on the crank pin slides along a lever
+/// pivoting on the ground `ground` to the right of the crank pivot.
+///
+/// The crank turns fully when it is shorter than `ground`.
+pub fn inverted_slider_crank(crank: f64, ground: f64, crank_angle: f64) -> Linkage {
+    let [crank_pivot, pin, lever_pivot, lever_end] = [0, 1, 2, 3];
+    let p = crank_pin(crank, crank_angle);
+    let pivot = (ground, 0.0);
+    let distance = (p.0 - pivot.0).hypot(p.1 - pivot.1);
+    let reach = ground + crank + RAIL_MARGIN;
+
+    Linkage {
+        joints: vec![
+            (0.0, 0.0),
+            p,
+            pivot,
+            (
+                pivot.0 + reach * (p.0 - pivot.0) / distance,
+                pivot.1 + reach * (p.1 - pivot.1) / distance,
+            ),
+        ],
+        bodies: vec![
+            vec![crank_pivot, lever_pivot],
+            vec![crank_pivot, pin],
+            vec![pin],
+            vec![lever_pivot, lever_end],
+        ],
+        sliders: vec![Slider {
+            guide: 3,
+            rail: (lever_pivot, lever_end),
+            block: 2,
+            joint: pin,
+        }],
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin,
+        }],
+    }
+}
+
+/// A Scotch yoke: a block on the crank pin slides in an upright slot of a
+/// yoke, which itself slides along the x-axis, so that the yoke moves
+/// harmonically.
+pub fn scotch_yoke(crank: f64, crank_angle: f64) -> Linkage {
+    let [crank_pivot, pin, slot_start, slot_end, yoke_block, rail_start, rail_end] =
+        [0, 1, 2, 3, 4, 5, 6];
+    let p = crank_pin(crank, crank_angle);
+    let half_slot = crank + RAIL_MARGIN;
+    // the yoke reaches far enough right for its block to clear the crank
+    let arm = 2.0 * crank + RAIL_MARGIN;
+
+    Linkage {
+        joints: vec![
+            (0.0, 0.0),
+            p,
+            (p.0, -half_slot),
+            (p.0, half_slot),
+            (p.0 + arm, 0.0),
+            (arm - crank - RAIL_MARGIN, 0.0),
+            (arm + crank + RAIL_MARGIN, 0.0),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rail_start, rail_end],
+            vec![crank_pivot, pin],
+            vec![pin],
+            vec![slot_start, slot_end, yoke_block],
+        ],
+        sliders: vec![
+            Slider {
+                guide: 3,
+                rail: (slot_start, slot_end),
+                block: 2,
+                joint: pin,
+            },
+            Slider {
+                guide: 0,
+                rail: (rail_start, rail_end),
+                block: 3,
+                joint: yoke_block,
+            },
+        ],
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin,
+        }],
+    }
+}
+
+/// A Watt I six-bar: a dyad joins the coupler point of the default
+/// four-bar to its rocker, so the two ternary links, coupler and rocker,
+/// are pinned to each other and the ground is binary.
+pub fn watt_i(crank_angle: f64) -> Linkage {
+    let [crank_pivot, crank_pin, rocker_pin, rocker_pivot, coupler_point] = [0, 1, 2, 3, 4];
+    let [rocker_point, knee, tracer] = [5, 6, 7];
+    let pose = four_bar_at(crank_angle);
+    let rocker = offset(pose.rocker_pivot, pose.rocker_pin, 0.5, -60.0);
+    let k = offset(pose.coupler_point, rocker, 0.5, 120.0);
+
+    Linkage {
+        joints: vec![
+            pose.crank_pivot,
+            pose.crank_pin,
+            pose.rocker_pin,
+            pose.rocker_pivot,
+            pose.coupler_point,
+            rocker,
+            k,
+            offset(pose.coupler_point, k, 0.5, 40.0),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rocker_pivot],
+            vec![crank_pivot, crank_pin],
+            vec![crank_pin, rocker_pin, coupler_point],
+            vec![rocker_pivot, rocker_pin, rocker_point],
+            vec![coupler_point, knee, tracer],
+            vec![knee, rocker_point],
+        ],
+        sliders: Vec::new(),
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        }],
+    }
+}
+
+/// A Watt II six-bar: two four-bars in series, the rocker of the default
+/// four-bar driving a second rocker through another coupler. Its ternary
+/// links are the ground and the first rocker.
+pub fn watt_ii(crank_angle: f64) -> Linkage {
+    let [crank_pivot, crank_pin, rocker_pin, rocker_pivot, coupler_point] = [0, 1, 2, 3, 4];
+    let [rocker_point, output_pivot, output_pin, tracer] = [5, 6, 7, 8];
+    let pose = four_bar_at(crank_angle);
+    let rocker = offset(pose.rocker_pivot, pose.rocker_pin, 0.6, 80.0);
+    let pivot = (450.0, 0.0);
+    let pin = offset(rocker, pivot, 0.5, 150.0);
+
+    Linkage {
+        joints: vec![
+            pose.crank_pivot,
+            pose.crank_pin,
+            pose.rocker_pin,
+            pose.rocker_pivot,
+            pose.coupler_point,
+            rocker,
+            pivot,
+            pin,
+            offset(rocker, pin, 0.5, 40.0),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rocker_pivot, output_pivot],
+            vec![crank_pivot, crank_pin],
+            vec![crank_pin, rocker_pin, coupler_point],
+            vec![rocker_pivot, rocker_pin, rocker_point],
+            vec![rocker_point, output_pin, tracer],
+            vec![output_pivot, output_pin],
+        ],
+        sliders: Vec::new(),
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        }],
+    }
+}
+
+/// A Stephenson I six-bar: a dyad joins the crank of the default four-bar
+/// to its rocker, so the ternary links, crank and rocker, both pivot on the
+/// binary ground.
+pub fn stephenson_i(crank_angle: f64) -> Linkage {
+    let [crank_pivot, crank_pin, rocker_pin, rocker_pivot, coupler_point] = [0, 1, 2, 3, 4];
+    let [crank_point, rocker_point, knee, tracer] = [5, 6, 7, 8];
+    let pose = four_bar_at(crank_angle);
+    let crank = offset(pose.crank_pivot, pose.crank_pin, 0.5, -60.0);
+    let rocker = offset(pose.rocker_pivot, pose.rocker_pin, 0.5, 60.0);
+    let k = offset(crank, rocker, 0.5, 150.0);
+
+    Linkage {
+        joints: vec![
+            pose.crank_pivot,
+            pose.crank_pin,
+            pose.rocker_pin,
+            pose.rocker_pivot,
+            pose.coupler_point,
+            crank,
+            rocker,
+            k,
+            offset(crank, k, 0.5, 40.0),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rocker_pivot],
+            vec![crank_pivot, crank_pin, crank_point],
+            vec![crank_pin, rocker_pin, coupler_point],
+            vec![rocker_pivot, rocker_pin, rocker_point],
+            vec![crank_point, knee, tracer],
+            vec![knee, rocker_point],
+        ],
+        sliders: Vec::new(),
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        }],
+    }
+}
+
+/// A Stephenson II six-bar: a dyad joins the coupler point of the default
+/// four-bar to a third pivot on the ground, so the ternary links are the
+/// ground and the coupler. This is the usual dwell mechanism: the output
+/// rests while the coupler point runs along an arc about the knee.
+pub fn stephenson_ii(crank_angle: f64) -> Linkage {
+    let [crank_pivot, crank_pin, rocker_pin, rocker_pivot, coupler_point] = [0, 1, 2, 3, 4];
+    let [output_pivot, knee, tracer] = [5, 6, 7];
+    let pose = four_bar_at(crank_angle);
+    let pivot = (150.0, -150.0);
+    let k = offset(pose.coupler_point, pivot, 0.5, 120.0);
+
+    Linkage {
+        joints: vec![
+            pose.crank_pivot,
+            pose.crank_pin,
+            pose.rocker_pin,
+            pose.rocker_pivot,
+            pose.coupler_point,
+            pivot,
+            k,
+            offset(pose.coupler_point, k, 0.5, 40.0),
+        ],
+        bodies: vec![
+            vec![crank_pivot, rocker_pivot, output_pivot],
+            vec![crank_pivot, crank_pin],
+            vec![crank_pin, rocker_pin, coupler_point],
+            vec![rocker_pivot, rocker_pin],
+            vec![coupler_point, knee, tracer],
+            vec![output_pivot, knee],
+        ],
+        sliders: Vec::new(),
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        }],
+    }
+}
+
+/// A Stephenson III six-bar: the crank pin of the default four-bar is
+/// replaced by a second four-bar loop, with the ternary crank as its frame
+/// and the ternary coupler as its coupler. The ground is binary and pinned
+/// to only one ternary link.
+pub fn stephenson_iii(crank_angle: f64) -> Linkage {
+    let [crank_pivot, rocker_pivot, crank_pin, crank_point] = [0, 1, 2, 3];
+    let [coupler_pin, coupler_joint, rocker_pin, tracer] = [4, 5, 6, 7];
+    let pose = four_bar_at(crank_angle);
+    let crank = offset(pose.crank_pivot, pose.crank_pin, 0.5, 60.0);
+    let pin = offset(pose.crank_pin, pose.rocker_pin, -0.2, -80.0);
+    let joint = offset(pin, crank, 0.2, 30.0);
+
+    Linkage {
+        joints: vec![
+            pose.crank_pivot,
+            pose.rocker_pivot,
+            pose.crank_pin,
+            crank,
+            pin,
+            joint,
+            pose.rocker_pin,
+            pose.coupler_point,
+        ],
+        bodies: vec![
+            vec![crank_pivot, rocker_pivot],
+            vec![crank_pivot, crank_pin, crank_point],
+            vec![crank_pin, coupler_pin],
+            vec![crank_point, coupler_joint],
+            vec![coupler_pin, coupler_joint, rocker_pin, tracer],
+            vec![rocker_pivot, rocker_pin],
+        ],
+        sliders: Vec::new(),
+        cranks: vec![Crank {
+            body: 1,
+            pivot: crank_pivot,
+            pin: crank_pin,
+        }],
+    }
+}
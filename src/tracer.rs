@@ -0,0 +1,101 @@
+//! Extra points carried along by the moving links, each tracing its own
+//! path.
+
+use serde::{Deserialize, Serialize};
+
+use crate::four_bar::{Link, Pose};
+use crate::geometry::{length, Point};
+use crate::linkage::{Linkage, GROUND};
+
+/// A point fixed to a moving link, drawn together with the path it traces.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tracer {
+    /// The link carrying the point, as an index into [`Linkage::bodies`].
+    /// The links of a four-bar are numbered as in [`Link::ALL`], so 1 is the
+    /// crank, 2 the coupler and 3 the rocker. The ground, [`GROUND`], traces
+    /// nothing and carries no tracer.
+    pub body: usize,
+    /// Distance along the link, measured from its first joint towards its
+    /// second.
+    pub along: f64,
+    /// Distance perpendicular to the link, positive to its left.
+    pub across: f64,
+    /// Red, green and blue, from 0 to 1.
+    pub color: (f64, f64, f64),
+    /// Whether the point and its path are drawn.
+    pub visible: bool,
+}
+
+impl Tracer {
+    /// Where the point is when its link runs from `start` to `end`.
+    pub fn place(&self, start: Point, end: Point) -> Point {
+        let distance = length(start, end);
+        if distance == 0.0 {
+            return start;
+        }
+        let u = ((end.0 - start.0) / distance, (end.1 - start.1) / distance);
+        (
+            start.0 + self.along * u.0 - self.across * u.1,
+            start.1 + self.along * u.1 + self.across * u.0,
+        )
+    }
+
+    /// The link of a four-bar carrying the point, if `body` is a moving
+    /// one.
+    pub fn link(&self) -> Option<Link> {
+        if self.body == GROUND {
+            return None;
+        }
+        Link::ALL.get(self.body).copied()
+    }
+
+    /// Position of the point on the four-bar in `pose`.
+    pub fn on_pose(&self, pose: &Pose) -> Option<Point> {
+        let (start, end) = pose.link(self.link()?);
+        Some(self.place(start, end))
+    }
+
+    /// Ends of the link carrying the point in `linkage`, or `None` if there
+    /// is no such moving body or it has a single joint to take no direction
+    /// from.
+    pub fn ends_in(&self, linkage: &Linkage) -> Option<(Point, Point)> {
+        if self.body == GROUND {
+            return None;
+        }
+        match linkage.bodies.get(self.body)?.as_slice() {
+            [start, end, ..] => Some((linkage.joints[*start], linkage.joints[*end])),
+            _ => None,
+        }
+    }
+
+    /// Position of the point on `linkage`.
+    pub fn on_linkage(&self, linkage: &Linkage) -> Option<Point> {
+        let (start, end) = self.ends_in(linkage)?;
+        Some(self.place(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates;
+
+    #[test]
+    fn ground_carries_no_tracer() {
+        let pose = templates::four_bar();
+        let linkage = Linkage::from_pose(&pose);
+        let tracer = |body| Tracer {
+            body,
+            along: 10.0,
+            across: 5.0,
+            color: (0.0, 0.0, 0.0),
+            visible: true,
+        };
+        assert_eq!(tracer(GROUND).link(), None);
+        assert_eq!(tracer(GROUND).on_pose(&pose), None);
+        assert_eq!(tracer(GROUND).on_linkage(&linkage), None);
+        assert_eq!(tracer(2).link(), Some(Link::Coupler));
+        assert!(tracer(2).on_pose(&pose).is_some());
+        assert!(tracer(2).on_linkage(&linkage).is_some());
+    }
+}
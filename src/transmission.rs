@@ -0,0 +1,74 @@
+//! Transmission angle of the four-bar linkage.
+//!
+//! The transmission angle is the angle between coupler and rocker at the
+//! rocker pin. Near 0° or 180° the coupler pushes almost along the rocker and
+//! little of the force turns it, so designs usually keep it within some
+//! margin of 90° over the whole cycle.
+
+use std::f64::consts::PI;
+
+use crate::four_bar::{FourBar, Pose};
+
+impl Pose {
+    /// Angle between coupler and rocker at the rocker pin, in radians
+    /// between 0 and π.
+    pub fn transmission_angle(&self) -> f64 {
+        let a = (
+            self.crank_pin.0 - self.rocker_pin.0,
+            self.crank_pin.1 - self.rocker_pin.1,
+        );
+        let b = (
+            self.rocker_pivot.0 - self.rocker_pin.0,
+            self.rocker_pivot.1 - self.rocker_pin.1,
+        );
+        (a.0 * b.1 - a.1 * b.0).abs().atan2(a.0 * b.0 + a.1 * b.1)
+    }
+}
+
+/// Extremes of the transmission angle over a crank revolution, in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransmissionAngles {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl TransmissionAngles {
+    /// The smallest angle between coupler and rocker over the cycle, no
+    /// matter on which side of 90° it occurs.
+    pub fn worst(&self) -> f64 {
+        self.min.min(PI - self.max)
+    }
+
+    /// Whether the transmission angle comes closer than `threshold` to a
+    /// straight line on either side somewhere in the cycle, i.e. whether
+    /// [`TransmissionAngles::worst`] is below it.
+    pub fn is_below(&self, threshold: f64) -> bool {
+        self.worst() < threshold
+    }
+}
+
+impl FourBar {
+    /// Finds the extremes of the transmission angle over one revolution of
+    /// the crank, sampled at `steps` positions.
+    ///
+    /// Crank angles at which the linkage cannot be assembled are skipped;
+    /// returns `None` if there are none it can.
+    pub fn transmission_angles(
+        &self,
+        crank_angle: f64,
+        steps: usize,
+    ) -> Option<TransmissionAngles> {
+        self.revolution(crank_angle, steps)
+            .filter_map(|(_, pose)| pose.ok())
+            .map(|pose| pose.transmission_angle())
+            .fold(None, |range, mu| {
+                Some(match range {
+                    None => TransmissionAngles { min: mu, max: mu },
+                    Some(TransmissionAngles { min, max }) => TransmissionAngles {
+                        min: min.min(mu),
+                        max: max.max(mu),
+                    },
+                })
+            })
+    }
+}